@@ -0,0 +1,173 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use honeycomb::hex_cell::HexCell;
+use honeycomb::hierarchical::HierarchicalPathfinder;
+use honeycomb::honeycomb::Honeycomb;
+use honeycomb::pathfinding::PathfindingContext;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const SIZE: usize = 200;
+
+fn honeycomb() -> Honeycomb<u8> {
+    let mut honeycomb = Honeycomb::new(SIZE);
+    for h in honeycomb.grid.clone() {
+        // A few walls, so that searches can't just run straight to their goal
+        if h.q % 7 != 0 || h.r % 5 == 0 {
+            honeycomb
+                .data
+                .insert(h, 1 + (h.q + h.r).rem_euclid(3) as u8);
+        }
+    }
+
+    honeycomb
+}
+
+/// Returns the cost of the cheapest path between two cells, found by an A* search that gets the
+/// neighbors of every cell from [`neighbors`]. This is how the pathfinders worked before they moved
+/// to allocation-free neighbors, so the two kinds of lookup can be compared inside a real search.
+fn search_cost<I: IntoIterator<Item = HexCell>>(
+    honeycomb: &Honeycomb<u8>,
+    from: HexCell,
+    to: HexCell,
+    neighbors: impl Fn(HexCell) -> I,
+) -> Option<usize> {
+    let mut costs = vec![usize::MAX; honeycomb.grid.len()];
+    let mut open = BinaryHeap::new();
+
+    let start = honeycomb.index_of(&from)?;
+    costs[start] = 0;
+    open.push(Reverse((0, start)));
+
+    while let Some(Reverse((_, curr))) = open.pop() {
+        let hex = honeycomb.grid[curr];
+        if hex == to {
+            return Some(costs[curr]);
+        }
+
+        let Some(curr_val) = honeycomb.data.get(&hex) else {
+            continue;
+        };
+
+        for neighbor in neighbors(hex) {
+            let (Some(next), Some(next_val)) =
+                (honeycomb.index_of(&neighbor), honeycomb.data.get(&neighbor))
+            else {
+                continue;
+            };
+
+            let cost = costs[curr] + (*curr_val + *next_val) as usize;
+            if cost < costs[next] {
+                costs[next] = cost;
+                open.push(Reverse((cost + neighbor.axial_dist_to(&to) * 2, next)));
+            }
+        }
+    }
+
+    None
+}
+
+fn neighbors(c: &mut Criterion) {
+    let honeycomb = honeycomb();
+
+    c.bench_function("neighbors_of", |b| {
+        b.iter(|| {
+            let mut count = 0;
+            for &h in &honeycomb.grid {
+                count += honeycomb.neighbors_of(black_box(h)).len();
+            }
+            count
+        })
+    });
+
+    c.bench_function("iter_neighbors_of", |b| {
+        b.iter(|| {
+            let mut count = 0;
+            for &h in &honeycomb.grid {
+                count += honeycomb.iter_neighbors_of(black_box(h)).count();
+            }
+            count
+        })
+    });
+
+    let from = HexCell::new(-(SIZE as i32), 0);
+    let to = HexCell::new(SIZE as i32, 0);
+
+    c.bench_function("search_neighbors_of", |b| {
+        b.iter(|| {
+            search_cost(&honeycomb, black_box(from), to, |h| {
+                honeycomb.neighbors_of(h)
+            })
+        })
+    });
+
+    c.bench_function("search_iter_neighbors_of", |b| {
+        b.iter(|| {
+            search_cost(&honeycomb, black_box(from), to, |h| {
+                honeycomb.iter_neighbors_of(h)
+            })
+        })
+    });
+}
+
+fn pathfinding(c: &mut Criterion) {
+    let honeycomb = honeycomb();
+    let from = HexCell::new(-(SIZE as i32), 0);
+    let to = HexCell::new(SIZE as i32, 0);
+    let cost_fn = |a: &u8, b: &u8, _: Option<&()>| (*a + *b) as usize;
+    let heuristic = |a: &HexCell, b: &HexCell| a.axial_dist_to(b) * 2;
+
+    c.bench_function("cheapest_path", |b| {
+        b.iter(|| honeycomb.cheapest_path(black_box(&from), black_box(&to), cost_fn, heuristic))
+    });
+
+    let mut context = PathfindingContext::with_capacity(honeycomb.grid.len());
+    c.bench_function("cheapest_path_with", |b| {
+        b.iter(|| {
+            honeycomb
+                .cheapest_path_with(
+                    &mut context,
+                    black_box(&from),
+                    black_box(&to),
+                    cost_fn,
+                    heuristic,
+                )
+                .map(|path| path.len())
+        })
+    });
+
+    c.bench_function("bidirectional_path_with", |b| {
+        b.iter(|| {
+            honeycomb
+                .bidirectional_path_with(
+                    &mut context,
+                    black_box(&from),
+                    black_box(&to),
+                    cost_fn,
+                    heuristic,
+                )
+                .map(|path| path.len())
+        })
+    });
+
+    let hierarchical = HierarchicalPathfinder::new(&honeycomb, 10, cost_fn);
+    c.bench_function("hierarchical_find_path", |b| {
+        b.iter(|| hierarchical.find_path(&honeycomb, black_box(&from), black_box(&to), heuristic))
+    });
+
+    c.bench_function("shortest_path_with", |b| {
+        b.iter(|| {
+            honeycomb
+                .shortest_path_with(&mut context, black_box(&from), black_box(&to), |_, _, _| {
+                    true
+                })
+                .map(|path| path.len())
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = neighbors, pathfinding
+}
+criterion_main!(benches);
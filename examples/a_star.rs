@@ -1,8 +1,7 @@
-mod utils;
-
-use crate::utils::camera::Camera;
+use honeycomb::cartesian_point::CartesianPoint;
 use honeycomb::hex_cell::HexCell;
 use honeycomb::honeycomb::Honeycomb;
+use honeycomb::viewport::Viewport;
 use itertools::Itertools;
 use rand::random;
 use speedy2d::color::Color;
@@ -16,7 +15,7 @@ const HEIGHT: u32 = 1200;
 
 struct State {
     honeycomb: Honeycomb<u8>,
-    camera: Camera,
+    viewport: Viewport,
     last_mouse_position: Vec2,
     start_hex: Option<HexCell>,
     end_hex: Option<HexCell>,
@@ -33,7 +32,7 @@ impl State {
 
         Self {
             honeycomb,
-            camera: Camera::new(0.0, 0.0, WIDTH as f32, HEIGHT as f32, 5.2),
+            viewport: Viewport::new(WIDTH as f32, HEIGHT as f32, 100.0 / 5.2),
             last_mouse_position: Vec2::new(-1.0, -1.0),
             start_hex: None,
             end_hex: None,
@@ -51,16 +50,16 @@ impl State {
             (-cos30, -0.5f32),
             (-cos30, 0.5f32),
         ]
-        .map(|(x, y)| (x * 100.0 / self.camera.scale, y * 100.0 / self.camera.scale));
+        .map(|(x, y)| (x * self.viewport.zoom, -y * self.viewport.zoom));
         let polygon = Polygon::new(&vertices);
-        let o = self.camera.world_to_screen(hex.world_location());
+        let o = self.viewport.world_to_screen(hex.world_location());
 
         graphics.draw_polygon(&polygon, (o.x, o.y), color);
     }
 
     fn draw_line(&self, from: &HexCell, to: &HexCell, graphics: &mut Graphics2D, color: Color) {
-        let screen_from = self.camera.world_to_screen(from.world_location());
-        let screen_to = self.camera.world_to_screen(to.world_location());
+        let screen_from = self.viewport.world_to_screen(from.world_location());
+        let screen_to = self.viewport.world_to_screen(to.world_location());
 
         graphics.draw_line(
             (screen_from.x, screen_from.y),
@@ -84,7 +83,12 @@ impl WindowHandler for State {
         if let (Some(start), Some(finish)) = (self.start_hex, self.end_hex) {
             let path = self
                 .honeycomb
-                .cheapest_path(&start, &finish, |_, new_val| *new_val as usize, |a, b| a.axial_dist_to(b))
+                .cheapest_path(
+                    &start,
+                    &finish,
+                    |_, new_val, _| *new_val as usize,
+                    |a, b| a.axial_dist_to(b),
+                )
                 .unwrap();
 
             for (h1, h2) in path.iter().tuple_windows() {
@@ -106,10 +110,12 @@ impl WindowHandler for State {
         _scancode: KeyScancode,
     ) {
         if let Some(keycode) = virtual_key_code {
-            let nearest_hex = self.honeycomb.world_to_hex(
-                self.camera
-                    .screen_to_world(self.last_mouse_position.x, self.last_mouse_position.y),
-            );
+            let nearest_hex =
+                self.honeycomb
+                    .world_to_hex(self.viewport.screen_to_world(CartesianPoint::new(
+                        self.last_mouse_position.x,
+                        self.last_mouse_position.y,
+                    )));
 
             match (keycode, self.start_hex, self.end_hex) {
                 (VirtualKeyCode::Space, Some(_), None) => self.end_hex = nearest_hex,
@@ -1,8 +1,6 @@
-mod utils;
-
-use crate::utils::camera::Camera;
 use honeycomb::hex_cell::HexCell;
 use honeycomb::honeycomb::Honeycomb;
+use honeycomb::viewport::Viewport;
 use rand::random;
 use speedy2d::color::Color;
 use speedy2d::window::{WindowHandler, WindowHelper};
@@ -13,7 +11,7 @@ const HEIGHT: u32 = 600;
 
 struct Handler {
     honeycomb: Honeycomb<u8>,
-    camera: Camera,
+    viewport: Viewport,
 }
 
 impl Handler {
@@ -26,7 +24,7 @@ impl Handler {
 
         Self {
             honeycomb,
-            camera: Camera::new(0.0, 0.0, WIDTH as f32, HEIGHT as f32, 2.0),
+            viewport: Viewport::new(WIDTH as f32, HEIGHT as f32, 100.0 / 2.0),
         }
     }
 
@@ -34,7 +32,7 @@ impl Handler {
         let vertices = hex
             .vertex_locations()
             .into_iter()
-            .map(|p| self.camera.world_to_screen(p))
+            .map(|p| self.viewport.world_to_screen(p))
             .map(|p| (p.x, p.y))
             .collect::<Vec<_>>();
 
@@ -45,7 +43,7 @@ impl Handler {
         graphics.draw_line(vertices[4], vertices[5], 4.0, color);
         graphics.draw_line(vertices[5], vertices[0], 4.0, color);
 
-        let center = self.camera.world_to_screen(hex.world_location());
+        let center = self.viewport.world_to_screen(hex.world_location());
         let radius = *self.honeycomb.data.get(hex).unwrap() as f32 / 16.0;
         graphics.draw_circle((center.x, center.y), radius, color);
     }
@@ -1,8 +1,7 @@
-mod utils;
-
-use crate::utils::camera::Camera;
+use honeycomb::cartesian_point::CartesianPoint;
 use honeycomb::hex_cell::HexCell;
 use honeycomb::honeycomb::Honeycomb;
+use honeycomb::viewport::Viewport;
 use speedy2d::color::Color;
 use speedy2d::dimen::Vec2;
 use speedy2d::window::{WindowHandler, WindowHelper};
@@ -13,7 +12,7 @@ const HEIGHT: u32 = 600;
 
 struct Handler {
     honeycomb: Honeycomb<u8>,
-    camera: Camera,
+    viewport: Viewport,
     target_hex: Option<HexCell>,
 }
 
@@ -21,7 +20,7 @@ impl Handler {
     fn new() -> Self {
         Self {
             honeycomb: Honeycomb::new(3),
-            camera: Camera::new(0.0, 0.0, WIDTH as f32, HEIGHT as f32, 2.0),
+            viewport: Viewport::new(WIDTH as f32, HEIGHT as f32, 100.0 / 2.0),
             target_hex: None,
         }
     }
@@ -30,7 +29,7 @@ impl Handler {
         let vertices = hex
             .vertex_locations()
             .into_iter()
-            .map(|p| self.camera.world_to_screen(p))
+            .map(|p| self.viewport.world_to_screen(p))
             .map(|p| (p.x, p.y))
             .collect::<Vec<_>>();
 
@@ -66,7 +65,9 @@ impl WindowHandler for Handler {
     }
 
     fn on_mouse_move(&mut self, _helper: &mut WindowHelper<()>, position: Vec2) {
-        let world_position = self.camera.screen_to_world(position.x, position.y);
+        let world_position = self
+            .viewport
+            .screen_to_world(CartesianPoint::new(position.x, position.y));
         self.target_hex = self.honeycomb.world_to_hex(world_position);
     }
 }
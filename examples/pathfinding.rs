@@ -1,8 +1,9 @@
-mod utils;
+#![allow(clippy::map_clone)]
 
-use crate::utils::camera::Camera;
+use honeycomb::cartesian_point::CartesianPoint;
 use honeycomb::hex_cell::HexCell;
 use honeycomb::honeycomb::Honeycomb;
+use honeycomb::viewport::Viewport;
 use rand::random;
 use speedy2d::color::Color;
 use speedy2d::dimen::Vec2;
@@ -14,7 +15,7 @@ const HEIGHT: u32 = 1200;
 
 struct State {
     honeycomb: Honeycomb<bool>,
-    camera: Camera,
+    viewport: Viewport,
     last_mouse_position: Vec2,
     start_hex: Option<HexCell>,
     end_hex: Option<HexCell>,
@@ -53,7 +54,7 @@ impl State {
 
         Self {
             honeycomb,
-            camera: Camera::new(0.0, 0.0, WIDTH as f32, HEIGHT as f32, 5.2),
+            viewport: Viewport::new(WIDTH as f32, HEIGHT as f32, 100.0 / 5.2),
             last_mouse_position: Vec2::new(-1.0, -1.0),
             start_hex: None,
             end_hex: None,
@@ -66,7 +67,7 @@ impl State {
         let vertices = hex
             .vertex_locations()
             .into_iter()
-            .map(|p| self.camera.world_to_screen(p))
+            .map(|p| self.viewport.world_to_screen(p))
             .map(|p| (p.x, p.y))
             .collect::<Vec<_>>();
 
@@ -100,7 +101,7 @@ impl WindowHandler for State {
         }
 
         if let (Some(a), Some(b)) = (self.start_hex, self.end_hex) {
-            let p = self.honeycomb.shortest_path(&a, &b, |_, x| *x);
+            let p = self.honeycomb.shortest_path(&a, &b, |_, x, _| *x);
 
             if let Some(path) = p {
                 for h in path {
@@ -123,10 +124,12 @@ impl WindowHandler for State {
         _scancode: KeyScancode,
     ) {
         if let Some(keycode) = virtual_key_code {
-            let nearest_hex = self.honeycomb.world_to_hex(
-                self.camera
-                    .screen_to_world(self.last_mouse_position.x, self.last_mouse_position.y),
-            );
+            let nearest_hex =
+                self.honeycomb
+                    .world_to_hex(self.viewport.screen_to_world(CartesianPoint::new(
+                        self.last_mouse_position.x,
+                        self.last_mouse_position.y,
+                    )));
 
             match (keycode, self.start_hex, self.end_hex) {
                 (VirtualKeyCode::Space, Some(_), None) => self.end_hex = nearest_hex,
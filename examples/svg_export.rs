@@ -0,0 +1,43 @@
+use honeycomb::hex_cell::HexCell;
+use honeycomb::honeycomb::Honeycomb;
+use honeycomb::layout::Layout;
+use honeycomb::svg::{CellStyle, PathOverlay, SvgOptions};
+use rand::random;
+
+fn main() {
+    let mut honeycomb = Honeycomb::<u8>::new(8);
+
+    for h in honeycomb.grid.iter() {
+        let val = random::<u8>() % 4 * 85;
+        honeycomb.data.insert(*h, val);
+    }
+
+    let (start, finish) = (HexCell::new(-8, 4), HexCell::new(8, -4));
+    let path = honeycomb
+        .cheapest_path(
+            &start,
+            &finish,
+            |_, new_val, _| *new_val as usize,
+            |a, b| a.axial_dist_to(b),
+        )
+        .unwrap();
+
+    let layout = Layout::fit(&honeycomb, 1200.0, 1000.0, 10.0);
+    let options = SvgOptions {
+        background: Some("white".to_string()),
+        paths: vec![PathOverlay::new(path, "green")],
+        coordinate_labels: true,
+    };
+
+    let svg = honeycomb.to_svg_with_options(
+        &layout,
+        |_, val| {
+            let intensity = 255 - *val.unwrap();
+            CellStyle::new(&format!("rgb({0},{0},{0})", intensity))
+        },
+        &options,
+    );
+
+    std::fs::write("honeycomb.svg", svg).unwrap();
+    println!("Wrote honeycomb.svg");
+}
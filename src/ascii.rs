@@ -0,0 +1,217 @@
+use crate::hex_cell::HexCell;
+use crate::honeycomb::Honeycomb;
+use std::fmt::{Display, Formatter};
+
+/// The character used for cells that hold no data
+pub const MISSING: char = '?';
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseAsciiError {
+    /// A honeycomb of size `n` always has `2n + 1` rows
+    EvenRowCount(usize),
+    /// The row at the given index does not have the right number of cells for its position
+    RowLength {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A cell in the row at the given index is more than a single character wide
+    InvalidCell { row: usize, cell: String },
+}
+
+impl Display for ParseAsciiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseAsciiError::EvenRowCount(rows) => {
+                write!(f, "expected an odd number of rows, found {}", rows)
+            }
+            ParseAsciiError::RowLength {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} should have {} cells, found {}",
+                row, expected, found
+            ),
+            ParseAsciiError::InvalidCell { row, cell } => {
+                write!(
+                    f,
+                    "row {} has a cell wider than one character: {:?}",
+                    row, cell
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseAsciiError {}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Draws this honeycomb as text, with one character per cell as chosen by [`render`].
+    ///
+    /// Each row of the text is a row of cells with the same `r`, starting from the top of the map.
+    /// Rows are staggered so that every cell sits between its neighbors in the rows above and
+    /// below, for example a honeycomb of size 1 is drawn as
+    ///
+    /// ```text
+    ///  . .
+    /// . . .
+    ///  . .
+    /// ```
+    ///
+    /// Cells that hold no data are drawn as `MISSING`.
+    pub fn to_ascii(&self, render: fn(val: &T) -> char) -> String {
+        let n = self.size() as i32;
+
+        (-n..=n)
+            .map(|r| {
+                let cells = row_range(n, r)
+                    .map(|q| match self.data.get(&HexCell::new(q, r)) {
+                        Some(val) => render(val).to_string(),
+                        None => MISSING.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "{}{}",
+                    " ".repeat(r.unsigned_abs() as usize),
+                    cells.join(" ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Builds a honeycomb from text in the layout written by `Honeycomb::to_ascii`.
+    ///
+    /// [`parse`] turns each character into the value of its cell, or `None` to leave the cell
+    /// without data. Leading and trailing blank lines and the indentation of each row are ignored,
+    /// so the text can be written inline in an indented string literal.
+    pub fn from_ascii(
+        text: &str,
+        parse: fn(c: char) -> Option<T>,
+    ) -> Result<Self, ParseAsciiError> {
+        let rows = text
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .skip_while(|row| row.is_empty())
+            .collect::<Vec<_>>();
+        let blank = rows.iter().rev().take_while(|row| row.is_empty()).count();
+        let rows = &rows[..rows.len() - blank];
+
+        if rows.len() % 2 == 0 {
+            return Err(ParseAsciiError::EvenRowCount(rows.len()));
+        }
+
+        let n = (rows.len() / 2) as i32;
+        let mut honeycomb = Honeycomb::new(n as usize);
+
+        for (i, (row, r)) in rows.iter().zip(-n..=n).enumerate() {
+            let expected = row_range(n, r).count();
+            if row.len() != expected {
+                return Err(ParseAsciiError::RowLength {
+                    row: i,
+                    expected,
+                    found: row.len(),
+                });
+            }
+
+            for (cell, q) in row.iter().zip(row_range(n, r)) {
+                let mut chars = cell.chars();
+
+                let c = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => {
+                        return Err(ParseAsciiError::InvalidCell {
+                            row: i,
+                            cell: cell.to_string(),
+                        })
+                    }
+                };
+
+                if let Some(val) = parse(c) {
+                    honeycomb.data.insert(HexCell::new(q, r), val);
+                }
+            }
+        }
+
+        Ok(honeycomb)
+    }
+}
+
+/// Returns the values of `q` in row `r` of a honeycomb of size `n`
+fn row_range(n: i32, r: i32) -> std::ops::RangeInclusive<i32> {
+    std::cmp::max(-n, -r - n)..=std::cmp::min(n, -r + n)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ascii::ParseAsciiError;
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "  # . .\n . . # .\n. . @ . .\n . # . .\n  . . #";
+        let honeycomb = Honeycomb::<char>::from_ascii(text, Some).unwrap();
+
+        assert_eq!(honeycomb.size(), 2);
+        assert_eq!(honeycomb.data.len(), honeycomb.grid.len());
+        assert_eq!(honeycomb.data.get(&HexCell::origin()), Some(&'@'));
+        assert_eq!(honeycomb.data.get(&HexCell::new(0, -2)), Some(&'#'));
+        assert_eq!(honeycomb.data.get(&HexCell::new(2, -2)), Some(&'.'));
+        assert_eq!(honeycomb.data.get(&HexCell::new(-1, 1)), Some(&'#'));
+
+        assert_eq!(honeycomb.to_ascii(|c| *c), text);
+    }
+
+    #[test]
+    fn test_missing() {
+        let honeycomb = Honeycomb::<bool>::from_ascii(
+            "
+                . #
+               # ? .
+                . .
+            ",
+            |c| match c {
+                '.' => Some(true),
+                '#' => Some(false),
+                _ => None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(honeycomb.data.len(), 6);
+        assert_eq!(honeycomb.data.get(&HexCell::origin()), None);
+        assert_eq!(
+            honeycomb.to_ascii(|b| if *b { '.' } else { '#' }),
+            " . #\n# ? .\n . ."
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let parse = |_| Some(());
+
+        assert_eq!(
+            Honeycomb::<()>::from_ascii(". .\n. . .", parse).err(),
+            Some(ParseAsciiError::EvenRowCount(2))
+        );
+        assert_eq!(
+            Honeycomb::<()>::from_ascii(" . .\n. .\n . .", parse).err(),
+            Some(ParseAsciiError::RowLength {
+                row: 1,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Honeycomb::<()>::from_ascii(" . .\n. .. .\n . .", parse).err(),
+            Some(ParseAsciiError::InvalidCell {
+                row: 1,
+                cell: "..".to_string()
+            })
+        );
+    }
+}
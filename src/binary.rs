@@ -0,0 +1,597 @@
+use crate::coord::Coord;
+use crate::hex_cell::{Hex, HexCell};
+use crate::hex_edge::{EdgeDirection, HexEdge};
+use crate::hex_vertex::{HexVertex, VertexDirection};
+use crate::honeycomb::{cell_count, Honeycomb};
+use crate::portal::Portal;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+
+/// The first four bytes of every honeycomb file
+pub const MAGIC: [u8; 4] = *b"HCMB";
+
+/// The newest version of the format, which is written by `Honeycomb::write_binary`. Version 2
+/// added portals, and files of version 1 are read as having none.
+pub const VERSION: u16 = 2;
+
+const SHAPE_HEXAGON: u8 = 0;
+const ORIENTATION_POINTY: u8 = 0;
+const FLAG_RUN_LENGTH: u8 = 1;
+
+/// A value that can be written to a honeycomb file
+pub trait Encode {
+    fn encode<W: Write>(&self, writer: &mut W) -> std::io::Result<()>;
+}
+
+/// A value that can be read back from a honeycomb file
+pub trait Decode: Sized {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError>;
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(std::io::Error),
+    /// The data does not start with `MAGIC`
+    InvalidMagic,
+    /// The file was written by a newer version of the format
+    UnsupportedVersion(u16),
+    /// The header names a grid shape that this version does not know about
+    UnsupportedShape(u8),
+    /// The header names a hex orientation that this version does not know about
+    UnsupportedOrientation(u8),
+    /// The payload does not match the header
+    InvalidData(&'static str),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Io(e) => write!(f, "{}", e),
+            DecodeError::InvalidMagic => write!(f, "not a honeycomb file"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            DecodeError::UnsupportedShape(s) => write!(f, "unsupported grid shape {}", s),
+            DecodeError::UnsupportedOrientation(o) => write!(f, "unsupported orientation {}", o),
+            DecodeError::InvalidData(msg) => write!(f, "invalid data: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DecodeError {
+    fn from(e: std::io::Error) -> Self {
+        DecodeError::Io(e)
+    }
+}
+
+impl<T: Encode, E: Encode, V: Encode> Honeycomb<T, E, V> {
+    /// Writes this honeycomb in the binary honeycomb format.
+    ///
+    /// The file starts with a header recording the format version, shape, orientation and size of
+    /// the grid, followed by a bitmap of which cells hold data and the data itself in the order of
+    /// `Honeycomb::cells()`. Data on edges and vertices touching the grid follows as a list of
+    /// key-value pairs, and the portals of the grid as a list after that.
+    ///
+    /// If [`run_length`] is set, consecutive cells whose values encode to the same bytes are
+    /// written once along with the length of the run, which is much smaller for maps with large
+    /// uniform areas.
+    ///
+    /// Fails with `std::io::ErrorKind::InvalidInput` if the size of the grid does not fit in the
+    /// 32 bits the header stores it in.
+    pub fn write_binary<W: Write>(&self, writer: &mut W, run_length: bool) -> std::io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        VERSION.encode(writer)?;
+        SHAPE_HEXAGON.encode(writer)?;
+        ORIENTATION_POINTY.encode(writer)?;
+        let size = u32::try_from(self.size()).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "grid size out of range")
+        })?;
+        size.encode(writer)?;
+        (if run_length { FLAG_RUN_LENGTH } else { 0 }).encode(writer)?;
+
+        let mut presence = vec![0u8; self.grid_len().div_ceil(8)];
+        for (i, h) in self.cells().enumerate() {
+            if self.data.contains_key(&h) {
+                presence[i / 8] |= 1 << (i % 8);
+            }
+        }
+        writer.write_all(&presence)?;
+
+        let values = self.cells().filter_map(|h| self.data.get(&h));
+        if run_length {
+            let mut run: Option<(Vec<u8>, u64)> = None;
+
+            for val in values {
+                let mut bytes = Vec::new();
+                val.encode(&mut bytes)?;
+
+                match &mut run {
+                    Some((prev, count)) if *prev == bytes => *count += 1,
+                    _ => {
+                        if let Some((prev, count)) = run.replace((bytes, 1)) {
+                            write_varint(writer, count)?;
+                            writer.write_all(&prev)?;
+                        }
+                    }
+                }
+            }
+
+            if let Some((prev, count)) = run {
+                write_varint(writer, count)?;
+                writer.write_all(&prev)?;
+            }
+        } else {
+            for val in values {
+                val.encode(writer)?;
+            }
+        }
+
+        let edges = self
+            .edges()
+            .into_iter()
+            .filter_map(|e| self.edge_data.get(&e).map(|val| (e, val)))
+            .collect::<Vec<_>>();
+
+        (edges.len() as u32).encode(writer)?;
+        for (e, val) in edges {
+            e.encode(writer)?;
+            val.encode(writer)?;
+        }
+
+        let vertices = self
+            .vertices()
+            .into_iter()
+            .filter_map(|v| self.vertex_data.get(&v).map(|val| (v, val)))
+            .collect::<Vec<_>>();
+
+        (vertices.len() as u32).encode(writer)?;
+        for (v, val) in vertices {
+            v.encode(writer)?;
+            val.encode(writer)?;
+        }
+
+        let portals = self
+            .cells()
+            .flat_map(|h| self.portals_from(&h))
+            .collect::<Vec<_>>();
+
+        (portals.len() as u32).encode(writer)?;
+        for portal in portals {
+            portal.encode(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Decode + Clone, E: Decode, V: Decode> Honeycomb<T, E, V> {
+    /// Reads a honeycomb written by `Honeycomb::write_binary`
+    pub fn read_binary<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(DecodeError::InvalidMagic);
+        }
+
+        let version = u16::decode(reader)?;
+        if version == 0 || version > VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let shape = u8::decode(reader)?;
+        if shape != SHAPE_HEXAGON {
+            return Err(DecodeError::UnsupportedShape(shape));
+        }
+
+        let orientation = u8::decode(reader)?;
+        if orientation != ORIENTATION_POINTY {
+            return Err(DecodeError::UnsupportedOrientation(orientation));
+        }
+
+        let size = u32::decode(reader)? as usize;
+        let flags = u8::decode(reader)?;
+
+        let cells = cell_count(size)
+            .filter(|_| size <= i32::MAX as usize)
+            .ok_or(DecodeError::InvalidData("grid size out of range"))?;
+
+        // The grid is only built once the whole bitmap has been read, so a header claiming a huge
+        // grid can't allocate more than the payload actually holds
+        let bitmap_len = cells.div_ceil(8);
+        let mut presence = Vec::new();
+        reader.take(bitmap_len as u64).read_to_end(&mut presence)?;
+        if presence.len() != bitmap_len {
+            return Err(DecodeError::InvalidData(
+                "presence bitmap does not fit the grid",
+            ));
+        }
+
+        let mut honeycomb = Honeycomb::new(size);
+
+        let present = honeycomb
+            .cells()
+            .enumerate()
+            .filter(|(i, _)| presence[i / 8] & (1 << (i % 8)) != 0)
+            .map(|(_, h)| h)
+            .collect::<Vec<_>>();
+
+        if flags & FLAG_RUN_LENGTH != 0 {
+            let mut cells = present.into_iter();
+
+            while cells.len() > 0 {
+                let count = read_varint(reader)?;
+                if count == 0 || count > cells.len() as u64 {
+                    return Err(DecodeError::InvalidData("run length does not fit the grid"));
+                }
+
+                let val = T::decode(reader)?;
+                for h in cells.by_ref().take(count as usize) {
+                    honeycomb.data.insert(h, val.clone());
+                }
+            }
+        } else {
+            for h in present {
+                honeycomb.data.insert(h, T::decode(reader)?);
+            }
+        }
+
+        for _ in 0..u32::decode(reader)? {
+            let e = HexEdge::decode(reader)?;
+            if !e.cells().iter().any(|h| honeycomb.index_of(h).is_some()) {
+                return Err(DecodeError::InvalidData("edge outside the grid"));
+            }
+            honeycomb.edge_data.insert(e, E::decode(reader)?);
+        }
+
+        for _ in 0..u32::decode(reader)? {
+            let v = HexVertex::decode(reader)?;
+            if !v.cells().iter().any(|h| honeycomb.index_of(h).is_some()) {
+                return Err(DecodeError::InvalidData("vertex outside the grid"));
+            }
+            honeycomb.vertex_data.insert(v, V::decode(reader)?);
+        }
+
+        if version >= 2 {
+            for _ in 0..u32::decode(reader)? {
+                if !honeycomb.add_portal(Portal::decode(reader)?) {
+                    return Err(DecodeError::InvalidData("portal outside the grid"));
+                }
+            }
+        }
+
+        Ok(honeycomb)
+    }
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut val: u64) -> std::io::Result<()> {
+    while val >= 0x80 {
+        writer.write_all(&[(val as u8) | 0x80])?;
+        val >>= 7;
+    }
+
+    writer.write_all(&[val as u8])
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> Result<u64, DecodeError> {
+    let mut val = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let byte = u8::decode(reader)?;
+        val |= ((byte & 0x7f) as u64) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(val);
+        }
+    }
+
+    Err(DecodeError::InvalidData("varint is too long"))
+}
+
+macro_rules! impl_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                fn encode<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+            }
+
+            impl Decode for $t {
+                fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+                    let mut bytes = [0u8; std::mem::size_of::<$t>()];
+                    reader.read_exact(&mut bytes)?;
+
+                    Ok(<$t>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl Encode for bool {
+    fn encode<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        (*self as u8).encode(writer)
+    }
+}
+
+impl Decode for bool {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::InvalidData("bool must be 0 or 1")),
+        }
+    }
+}
+
+impl Encode for () {
+    fn encode<W: Write>(&self, _writer: &mut W) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Decode for () {
+    fn decode<R: Read>(_reader: &mut R) -> Result<Self, DecodeError> {
+        Ok(())
+    }
+}
+
+impl<C: Coord + Encode> Encode for Hex<C> {
+    fn encode<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.q.encode(writer)?;
+        self.r.encode(writer)
+    }
+}
+
+impl<C: Coord + Decode> Decode for Hex<C> {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let q = C::decode(reader)?;
+        let r = C::decode(reader)?;
+
+        Ok(Hex::new(q, r))
+    }
+}
+
+impl Encode for HexEdge {
+    fn encode<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.cell.encode(writer)?;
+        (self.direction.index() as u8).encode(writer)
+    }
+}
+
+impl Decode for HexEdge {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let cell = HexCell::decode(reader)?;
+        let direction = match u8::decode(reader)? {
+            0 => EdgeDirection::East,
+            1 => EdgeDirection::NorthEast,
+            2 => EdgeDirection::NorthWest,
+            _ => return Err(DecodeError::InvalidData("unknown edge direction")),
+        };
+
+        Ok(HexEdge::new(cell, direction))
+    }
+}
+
+impl Encode for Portal {
+    fn encode<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.from.encode(writer)?;
+        self.to.encode(writer)?;
+        match self.cost {
+            Some(cost) => {
+                1u8.encode(writer)?;
+                (cost as u64).encode(writer)
+            }
+            None => 0u8.encode(writer),
+        }
+    }
+}
+
+impl Decode for Portal {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let from = HexCell::decode(reader)?;
+        let to = HexCell::decode(reader)?;
+        let cost = match u8::decode(reader)? {
+            0 => None,
+            1 => Some(
+                usize::try_from(u64::decode(reader)?)
+                    .map_err(|_| DecodeError::InvalidData("portal cost out of range"))?,
+            ),
+            _ => return Err(DecodeError::InvalidData("portal cost flag must be 0 or 1")),
+        };
+
+        Ok(Portal::new(from, to, cost))
+    }
+}
+
+impl Encode for HexVertex {
+    fn encode<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.cell.encode(writer)?;
+        match self.direction {
+            VertexDirection::North => 0u8.encode(writer),
+            VertexDirection::South => 1u8.encode(writer),
+        }
+    }
+}
+
+impl Decode for HexVertex {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let cell = HexCell::decode(reader)?;
+        let direction = match u8::decode(reader)? {
+            0 => VertexDirection::North,
+            1 => VertexDirection::South,
+            _ => return Err(DecodeError::InvalidData("unknown vertex direction")),
+        };
+
+        Ok(HexVertex::new(cell, direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binary::DecodeError;
+    use crate::hex_cell::HexCell;
+    use crate::hex_edge::HexEdge;
+    use crate::hex_vertex::HexVertex;
+    use crate::honeycomb::Honeycomb;
+    use crate::portal::Portal;
+
+    fn terrain() -> Honeycomb<u16, (), bool> {
+        let mut honeycomb = Honeycomb::new(10);
+
+        for h in honeycomb.grid.iter() {
+            let val = if h.q < 0 {
+                7
+            } else {
+                h.r.unsigned_abs() as u16
+            };
+            honeycomb.data.insert(*h, val);
+        }
+        honeycomb.data.remove(&HexCell::origin());
+        honeycomb
+            .vertex_data
+            .insert(HexVertex::from_corner(HexCell::new(2, 1), 4), true);
+        honeycomb.add_portal(Portal::new(
+            HexCell::new(-3, 2),
+            HexCell::new(5, 0),
+            Some(7),
+        ));
+
+        honeycomb
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let honeycomb = terrain();
+
+        for run_length in [false, true] {
+            let mut bytes = Vec::new();
+            honeycomb.write_binary(&mut bytes, run_length).unwrap();
+
+            let loaded = Honeycomb::<u16, (), bool>::read_binary(&mut bytes.as_slice()).unwrap();
+
+            assert_eq!(loaded.size(), honeycomb.size());
+            assert_eq!(loaded.data, honeycomb.data);
+            assert_eq!(loaded.vertex_data, honeycomb.vertex_data);
+            assert_eq!(
+                loaded.portals().collect::<Vec<_>>(),
+                honeycomb.portals().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_version_1() {
+        let mut honeycomb = terrain();
+        honeycomb.remove_portal(&HexCell::new(-3, 2), &HexCell::new(5, 0));
+
+        let mut bytes = Vec::new();
+        honeycomb.write_binary(&mut bytes, false).unwrap();
+
+        // Files of the first version end right after the vertex data, without a list of portals
+        bytes[4..6].copy_from_slice(&1u16.to_le_bytes());
+        bytes.truncate(bytes.len() - 4);
+
+        let loaded = Honeycomb::<u16, (), bool>::read_binary(&mut bytes.as_slice()).unwrap();
+        assert_eq!(loaded.data, honeycomb.data);
+        assert_eq!(loaded.vertex_data, honeycomb.vertex_data);
+        assert_eq!(loaded.portals().count(), 0);
+    }
+
+    #[test]
+    fn test_run_length() {
+        let mut honeycomb = Honeycomb::<u32>::new(20);
+
+        for h in honeycomb.grid.iter() {
+            honeycomb.data.insert(*h, 42);
+        }
+
+        let mut dense = Vec::new();
+        honeycomb.write_binary(&mut dense, false).unwrap();
+
+        let mut compressed = Vec::new();
+        honeycomb.write_binary(&mut compressed, true).unwrap();
+
+        assert!(compressed.len() * 10 < dense.len());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let mut bytes = Vec::new();
+        terrain().write_binary(&mut bytes, true).unwrap();
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(matches!(
+            Honeycomb::<u16, (), bool>::read_binary(&mut wrong_magic.as_slice()),
+            Err(DecodeError::InvalidMagic)
+        ));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 99;
+        assert!(matches!(
+            Honeycomb::<u16, (), bool>::read_binary(&mut wrong_version.as_slice()),
+            Err(DecodeError::UnsupportedVersion(99))
+        ));
+
+        let truncated = &bytes[..bytes.len() - 3];
+        assert!(matches!(
+            Honeycomb::<u16, (), bool>::read_binary(&mut &truncated[..]),
+            Err(DecodeError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_huge_size() {
+        let mut bytes = Vec::new();
+        terrain().write_binary(&mut bytes, false).unwrap();
+
+        // The size is stored right after the magic, version, shape and orientation
+        for size in [u32::MAX, 1 << 30] {
+            let mut huge = bytes[..20].to_vec();
+            huge[8..12].copy_from_slice(&size.to_le_bytes());
+
+            assert!(matches!(
+                Honeycomb::<u16, (), bool>::read_binary(&mut huge.as_slice()),
+                Err(DecodeError::InvalidData(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_outside() {
+        let mut honeycomb = Honeycomb::<u8, u8, u8>::new(2);
+        honeycomb
+            .edge_data
+            .insert(HexEdge::from_direction(HexCell::origin(), 0), 1);
+        honeycomb
+            .vertex_data
+            .insert(HexVertex::from_corner(HexCell::origin(), 0), 1);
+
+        let mut bytes = Vec::new();
+        honeycomb.write_binary(&mut bytes, false).unwrap();
+        assert!(Honeycomb::<u8, u8, u8>::read_binary(&mut bytes.as_slice()).is_ok());
+
+        // The edge and the vertex are the last two entries before the empty list of portals,
+        // each a cell, a direction and a value
+        let end = bytes.len() - 4;
+        for cell in [end - 10, end - 24] {
+            let mut moved = bytes.clone();
+            moved[cell..cell + 4].copy_from_slice(&10i32.to_le_bytes());
+
+            assert!(matches!(
+                Honeycomb::<u8, u8, u8>::read_binary(&mut moved.as_slice()),
+                Err(DecodeError::InvalidData(_))
+            ));
+        }
+    }
+}
@@ -1,45 +1,463 @@
-use std::ops::{Add, Sub};
+use std::fmt::Debug;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
+/// A floating point type that can be used for the coordinates of a `CartesianPoint`.
+///
+/// This is implemented for `f32`, which is precise enough for maps spanning a few thousand cells,
+/// and `f64` for maps where cells far from the origin need to be located precisely.
+pub trait Float:
+    Copy
+    + Debug
+    + Default
+    + PartialEq
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    /// Returns the given value rounded to the nearest value of this type
+    fn from_f64(value: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+
+    fn sqrt(self) -> Self;
+
+    fn abs(self) -> Self;
+
+    fn round(self) -> Self;
+
+    fn floor(self) -> Self;
+
+    fn ceil(self) -> Self;
+
+    fn sin_cos(self) -> (Self, Self);
+
+    fn min(self, other: Self) -> Self;
+
+    fn max(self, other: Self) -> Self;
+
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! impl_float {
+    ($($t:ident),*) => {
+        $(
+            impl Float for $t {
+                const ZERO: Self = 0.0;
+                const ONE: Self = 1.0;
+                const MIN: Self = $t::MIN;
+                const MAX: Self = $t::MAX;
+
+                fn from_f64(value: f64) -> Self {
+                    value as $t
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                fn sqrt(self) -> Self {
+                    $t::sqrt(self)
+                }
+
+                fn abs(self) -> Self {
+                    $t::abs(self)
+                }
+
+                fn round(self) -> Self {
+                    $t::round(self)
+                }
+
+                fn floor(self) -> Self {
+                    $t::floor(self)
+                }
+
+                fn ceil(self) -> Self {
+                    $t::ceil(self)
+                }
+
+                fn sin_cos(self) -> (Self, Self) {
+                    $t::sin_cos(self)
+                }
+
+                fn min(self, other: Self) -> Self {
+                    $t::min(self, other)
+                }
+
+                fn max(self, other: Self) -> Self {
+                    $t::max(self, other)
+                }
+
+                fn is_finite(self) -> bool {
+                    $t::is_finite(self)
+                }
+            }
+
+            impl Mul<CartesianPoint<$t>> for $t {
+                type Output = CartesianPoint<$t>;
+
+                fn mul(self, rhs: CartesianPoint<$t>) -> Self::Output {
+                    rhs * self
+                }
+            }
+        )*
+    };
+}
+
+impl_float!(f32, f64);
+
+/// A point in world space, with `f32` coordinates by default
 #[derive(Copy, Clone, Debug, PartialEq)]
-pub struct CartesianPoint {
-    pub x: f32,
-    pub y: f32,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CartesianPoint<F = f32> {
+    pub x: F,
+    pub y: F,
 }
 
-impl CartesianPoint {
-    pub fn new(x: f32, y: f32) -> Self {
+pub type CartesianPoint64 = CartesianPoint<f64>;
+
+impl<F: Float> CartesianPoint<F> {
+    pub fn new(x: F, y: F) -> Self {
         Self { x, y }
     }
+
+    /// Returns the dot product of this point and [`other`], treating both as vectors
+    pub fn dot(&self, other: &CartesianPoint<F>) -> F {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Returns the distance from the origin to this point
+    pub fn length(&self) -> F {
+        self.dot(self).sqrt()
+    }
+
+    /// Returns a point in the same direction from the origin as this point, at a distance of 1.
+    /// The origin itself has no direction, so normalizing it returns `None`.
+    pub fn normalize(&self) -> Option<CartesianPoint<F>> {
+        let length = self.length();
+
+        if length > F::ZERO && length.is_finite() {
+            Some(*self / length)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the point a fraction [`t`] of the way from this point to [`other`]. Values of [`t`]
+    /// outside of `0..=1` extrapolate past either end.
+    pub fn lerp(&self, other: &CartesianPoint<F>, t: F) -> CartesianPoint<F> {
+        CartesianPoint::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+        )
+    }
+
+    /// Returns the straight line distance between this point and [`other`]
+    pub fn distance(&self, other: &CartesianPoint<F>) -> F {
+        (other - self).length()
+    }
+
+    /// Returns whether both coordinates of this point are within [`epsilon`] of those of [`other`]
+    pub fn approx_eq(&self, other: &CartesianPoint<F>, epsilon: F) -> bool {
+        (self.x - other.x).abs() <= epsilon && (self.y - other.y).abs() <= epsilon
+    }
 }
 
-impl Add for CartesianPoint {
-    type Output = CartesianPoint;
+impl<F: Float> Add for CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
 
     fn add(self, rhs: Self) -> Self::Output {
         CartesianPoint::new(self.x + rhs.x, self.y + rhs.y)
     }
 }
 
-impl Add for &CartesianPoint {
-    type Output = CartesianPoint;
+impl<F: Float> Add for &CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
 
     fn add(self, rhs: Self) -> Self::Output {
         CartesianPoint::new(self.x + rhs.x, self.y + rhs.y)
     }
 }
 
-impl Sub for CartesianPoint {
-    type Output = CartesianPoint;
+impl<F: Float> AddAssign for CartesianPoint<F> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl<F: Float> Sub for CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         CartesianPoint::new(self.x - rhs.x, self.y - rhs.y)
     }
 }
 
-impl Sub for &CartesianPoint {
-    type Output = CartesianPoint;
+impl<F: Float> Sub for &CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         CartesianPoint::new(self.x - rhs.x, self.y - rhs.y)
     }
 }
+
+impl<F: Float> SubAssign for CartesianPoint<F> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+impl<F: Float> Mul<F> for CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        CartesianPoint::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl<F: Float> Mul<F> for &CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        CartesianPoint::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl<F: Float> MulAssign<F> for CartesianPoint<F> {
+    fn mul_assign(&mut self, rhs: F) {
+        self.x *= rhs;
+        self.y *= rhs;
+    }
+}
+
+impl<F: Float> Div<F> for CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
+
+    fn div(self, rhs: F) -> Self::Output {
+        CartesianPoint::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl<F: Float> Div<F> for &CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
+
+    fn div(self, rhs: F) -> Self::Output {
+        CartesianPoint::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl<F: Float> DivAssign<F> for CartesianPoint<F> {
+    fn div_assign(&mut self, rhs: F) {
+        self.x /= rhs;
+        self.y /= rhs;
+    }
+}
+
+impl<F: Float> Neg for CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
+
+    fn neg(self) -> Self::Output {
+        CartesianPoint::new(-self.x, -self.y)
+    }
+}
+
+impl<F: Float> Neg for &CartesianPoint<F> {
+    type Output = CartesianPoint<F>;
+
+    fn neg(self) -> Self::Output {
+        CartesianPoint::new(-self.x, -self.y)
+    }
+}
+
+impl<F: Float> From<[F; 2]> for CartesianPoint<F> {
+    fn from(value: [F; 2]) -> Self {
+        CartesianPoint::new(value[0], value[1])
+    }
+}
+
+impl<F: Float> From<CartesianPoint<F>> for [F; 2] {
+    fn from(value: CartesianPoint<F>) -> Self {
+        [value.x, value.y]
+    }
+}
+
+impl From<CartesianPoint<f32>> for CartesianPoint<f64> {
+    fn from(value: CartesianPoint<f32>) -> Self {
+        CartesianPoint::new(value.x as f64, value.y as f64)
+    }
+}
+
+#[cfg(feature = "mint")]
+mod mint_conversions {
+    use crate::cartesian_point::{CartesianPoint, Float};
+
+    impl<F: Float> From<mint::Vector2<F>> for CartesianPoint<F> {
+        fn from(value: mint::Vector2<F>) -> Self {
+            CartesianPoint::new(value.x, value.y)
+        }
+    }
+
+    impl<F: Float> From<CartesianPoint<F>> for mint::Vector2<F> {
+        fn from(value: CartesianPoint<F>) -> Self {
+            mint::Vector2 {
+                x: value.x,
+                y: value.y,
+            }
+        }
+    }
+
+    impl<F: Float> From<mint::Point2<F>> for CartesianPoint<F> {
+        fn from(value: mint::Point2<F>) -> Self {
+            CartesianPoint::new(value.x, value.y)
+        }
+    }
+
+    impl<F: Float> From<CartesianPoint<F>> for mint::Point2<F> {
+        fn from(value: CartesianPoint<F>) -> Self {
+            mint::Point2 {
+                x: value.x,
+                y: value.y,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "glam")]
+mod glam_conversions {
+    use crate::cartesian_point::CartesianPoint;
+
+    impl From<glam::Vec2> for CartesianPoint<f32> {
+        fn from(value: glam::Vec2) -> Self {
+            CartesianPoint::new(value.x, value.y)
+        }
+    }
+
+    impl From<CartesianPoint<f32>> for glam::Vec2 {
+        fn from(value: CartesianPoint<f32>) -> Self {
+            glam::Vec2::new(value.x, value.y)
+        }
+    }
+
+    impl From<glam::DVec2> for CartesianPoint<f64> {
+        fn from(value: glam::DVec2) -> Self {
+            CartesianPoint::new(value.x, value.y)
+        }
+    }
+
+    impl From<CartesianPoint<f64>> for glam::DVec2 {
+        fn from(value: CartesianPoint<f64>) -> Self {
+            glam::DVec2::new(value.x, value.y)
+        }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_conversions {
+    use crate::cartesian_point::{CartesianPoint, Float};
+
+    impl<F: Float + nalgebra::Scalar> From<nalgebra::Vector2<F>> for CartesianPoint<F> {
+        fn from(value: nalgebra::Vector2<F>) -> Self {
+            CartesianPoint::new(value.x, value.y)
+        }
+    }
+
+    impl<F: Float + nalgebra::Scalar> From<CartesianPoint<F>> for nalgebra::Vector2<F> {
+        fn from(value: CartesianPoint<F>) -> Self {
+            nalgebra::Vector2::new(value.x, value.y)
+        }
+    }
+
+    impl<F: Float + nalgebra::Scalar> From<nalgebra::Point2<F>> for CartesianPoint<F> {
+        fn from(value: nalgebra::Point2<F>) -> Self {
+            CartesianPoint::new(value.x, value.y)
+        }
+    }
+
+    impl<F: Float + nalgebra::Scalar> From<CartesianPoint<F>> for nalgebra::Point2<F> {
+        fn from(value: CartesianPoint<F>) -> Self {
+            nalgebra::Point2::new(value.x, value.y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartesian_point::CartesianPoint;
+
+    #[test]
+    fn test_operators() {
+        let a = CartesianPoint::new(3.0, -4.0);
+        let b = CartesianPoint::new(0.5, 2.0);
+
+        assert_eq!(a * 2.0, CartesianPoint::new(6.0, -8.0));
+        assert_eq!(2.0 * a, a * 2.0);
+        assert_eq!(&a / 2.0, CartesianPoint::new(1.5, -2.0));
+        assert_eq!(-a, CartesianPoint::new(-3.0, 4.0));
+
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        c -= b;
+        assert_eq!(c, a);
+        c *= 4.0;
+        c /= 2.0;
+        assert_eq!(c, a * 2.0);
+    }
+
+    #[test]
+    fn test_vector_math() {
+        let a = CartesianPoint::new(3.0, -4.0);
+        let b = CartesianPoint::new(0.5, 2.0);
+
+        assert_eq!(a.dot(&b), -6.5);
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.distance(&CartesianPoint::new(0.0, 0.0)), 5.0);
+        assert!(a
+            .normalize()
+            .unwrap()
+            .approx_eq(&CartesianPoint::new(0.6, -0.8), 1e-6));
+        assert_eq!(CartesianPoint::new(0.0, 0.0).normalize(), None);
+
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), CartesianPoint::new(1.75, -1.0));
+
+        assert!(a.approx_eq(&CartesianPoint::new(3.0001, -4.0001), 1e-3));
+        assert!(!a.approx_eq(&CartesianPoint::new(3.01, -4.0), 1e-3));
+    }
+
+    #[cfg(all(feature = "mint", feature = "glam", feature = "nalgebra"))]
+    #[test]
+    fn test_conversions() {
+        use crate::cartesian_point::CartesianPoint64;
+
+        let a = CartesianPoint::new(1.5, -2.5);
+
+        assert_eq!(CartesianPoint::from(mint::Vector2::from(a)), a);
+        assert_eq!(CartesianPoint::from(mint::Point2::from(a)), a);
+        assert_eq!(glam::Vec2::from(a), glam::Vec2::new(1.5, -2.5));
+        assert_eq!(CartesianPoint::from(glam::Vec2::from(a)), a);
+        assert_eq!(
+            glam::DVec2::from(CartesianPoint64::from(a)),
+            glam::DVec2::new(1.5, -2.5)
+        );
+        assert_eq!(
+            nalgebra::Vector2::from(a),
+            nalgebra::Vector2::new(1.5, -2.5)
+        );
+        assert_eq!(CartesianPoint::from(nalgebra::Point2::from(a)), a);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::hex_cell::HexCell;
+use crate::honeycomb::Honeycomb;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// The cells occupied by agents at every timestep, used to plan paths that never run into each
+/// other.
+///
+/// Agents are identified by any `usize` the caller chooses. Once an agent reaches the end of its
+/// path it is assumed to stay there for good.
+#[derive(Debug, Clone, Default)]
+pub struct ReservationTable {
+    /// The agent occupying every cell at every timestep while it is moving
+    moving: HashMap<(HexCell, usize), usize>,
+    /// The agent that has stopped on every cell, and the timestep from which it stays there
+    stopped: HashMap<HexCell, (usize, usize)>,
+    /// The last timestep at which every cell is occupied by a moving agent
+    last_moving: HashMap<HexCell, usize>,
+}
+
+impl ReservationTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves the cells of a path for the given agent, where `path[t]` is the cell it occupies
+    /// at timestep `t`. The agent stays on the last cell of the path from then on.
+    pub fn reserve_path(&mut self, agent: usize, path: &[HexCell]) {
+        let Some((last, moving)) = path.split_last() else {
+            return;
+        };
+
+        for (time, hex) in moving.iter().enumerate() {
+            self.moving.insert((*hex, time), agent);
+
+            let last_moving = self.last_moving.entry(*hex).or_insert(time);
+            *last_moving = time.max(*last_moving);
+        }
+
+        self.stopped.insert(*last, (agent, moving.len()));
+    }
+
+    /// Returns the agent occupying the given cell at the given timestep, if there is one
+    pub fn occupant(&self, hex: &HexCell, time: usize) -> Option<usize> {
+        if let Some(agent) = self.moving.get(&(*hex, time)) {
+            return Some(*agent);
+        }
+
+        self.stopped
+            .get(hex)
+            .and_then(|(agent, since)| (*since <= time).then_some(*agent))
+    }
+
+    /// Returns whether any agent occupies the given cell at any timestep
+    fn is_reserved(&self, hex: &HexCell) -> bool {
+        self.last_moving.contains_key(hex) || self.stopped.contains_key(hex)
+    }
+
+    /// Returns whether an agent could stop on the given cell at the given timestep and stay there
+    /// for good without being run into
+    fn can_stop(&self, hex: &HexCell, time: usize) -> bool {
+        self.occupant(hex, time).is_none()
+            && !self.stopped.contains_key(hex)
+            && self.last_moving.get(hex).is_none_or(|last| *last < time)
+    }
+}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Calculates the cheapest path from `from` to `to` that avoids every agent in
+    /// [`reservations`], or `None` if no such path ends within [`max_time`] timesteps.
+    ///
+    /// The path holds the cell occupied at every timestep, starting with `from` at timestep 0. Each
+    /// timestep the agent either steps into a neighboring cell, steps through a portal or waits
+    /// where it is, but it never enters a cell occupied at the same timestep, never swaps cells
+    /// with another agent, and only stops on `to` once no other agent will pass through it again.
+    ///
+    /// [`cost_fn`] is also called for every timestep spent waiting, as a step from the current cell
+    /// into itself without an edge value, so waiting is never free unless it returns 0 for that.
+    /// [`heuristic`] only estimates the cost of moving, and ignores any waiting still ahead.
+    pub fn cooperative_path(
+        &self,
+        reservations: &ReservationTable,
+        from: &HexCell,
+        to: &HexCell,
+        max_time: usize,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+    ) -> Option<Vec<HexCell>> {
+        let start = self.index_of(from)?;
+        self.index_of(to)?;
+
+        if reservations.occupant(from, 0).is_some() {
+            return None;
+        }
+
+        // Cells are searched together with the timestep at which they are reached
+        let mut reached = HashMap::from([((*from, 0), (0, *from))]);
+        let mut open = BinaryHeap::from([Reverse((heuristic(from, to), 0, start))]);
+
+        while let Some(Reverse((_, time, curr))) = open.pop() {
+            let hex = self.cell_at(curr);
+
+            if hex == *to && reservations.can_stop(&hex, time) {
+                let mut path = vec![hex];
+                for t in (1..=time).rev() {
+                    path.push(reached[&(path[path.len() - 1], t)].1);
+                }
+                path.reverse();
+
+                return Some(path);
+            }
+
+            if time >= max_time {
+                continue;
+            }
+
+            let Some(curr_val) = self.data.get(&hex) else {
+                continue;
+            };
+            let curr_cost = reached[&(hex, time)].0;
+
+            let wait = (curr, hex, cost_fn(curr_val, curr_val, None));
+            let steps = self.steps(hex, false).filter_map(|(neighbor, next, step)| {
+                let next_val = self.data.get(&neighbor)?;
+                Some((
+                    next,
+                    neighbor,
+                    self.step_cost(curr_val, next_val, step, cost_fn),
+                ))
+            });
+
+            for (next, neighbor, cost) in std::iter::once(wait).chain(steps) {
+                if reservations.occupant(&neighbor, time + 1).is_some() {
+                    continue;
+                }
+
+                // Two agents swapping cells would pass through each other
+                let swapping_with = reservations.occupant(&neighbor, time);
+                if swapping_with.is_some() && swapping_with == reservations.occupant(&hex, time + 1)
+                {
+                    continue;
+                }
+
+                let new_cost = curr_cost + cost;
+                let state = (neighbor, time + 1);
+
+                if reached.get(&state).is_none_or(|(c, _)| new_cost < *c) {
+                    reached.insert(state, (new_cost, hex));
+
+                    let priority = new_cost + heuristic(&neighbor, to);
+                    open.push(Reverse((priority, time + 1, next)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Plans paths for several agents in turn, each avoiding the ones planned before it, and
+    /// returns them in the same order as [`agents`]. Agent `i` of the result moves from
+    /// `agents[i].0` to `agents[i].1`, and is identified by `i` in the returned reservations.
+    ///
+    /// Each path is the same as that of `Honeycomb::cooperative_path`. Agents planned earlier take
+    /// priority, so the order of [`agents`] matters, and an agent can end up without a path even
+    /// though one would have been found in a different order. Agents without a path are assumed to
+    /// stay where they start, and every other path avoids them, even those planned before them.
+    pub fn cooperative_paths(
+        &self,
+        agents: &[(HexCell, HexCell)],
+        max_time: usize,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+    ) -> (Vec<Option<Vec<HexCell>>>, ReservationTable) {
+        let mut stranded = vec![false; agents.len()];
+
+        'plan: loop {
+            // Agents known to have no path are reserved before any other
+            let mut reservations = ReservationTable::new();
+            for (agent, (from, _)) in agents.iter().enumerate() {
+                if stranded[agent] {
+                    reservations.reserve_path(agent, &[*from]);
+                }
+            }
+
+            let mut paths = Vec::with_capacity(agents.len());
+            for (agent, (from, to)) in agents.iter().enumerate() {
+                if stranded[agent] {
+                    paths.push(None);
+                    continue;
+                }
+
+                let path =
+                    self.cooperative_path(&reservations, from, to, max_time, cost_fn, heuristic);
+
+                match &path {
+                    Some(path) => reservations.reserve_path(agent, path),
+                    None => {
+                        // An agent planned earlier may pass through the cell this one stays on,
+                        // in which case everyone is planned again around it
+                        stranded[agent] = true;
+                        if reservations.is_reserved(from) {
+                            continue 'plan;
+                        }
+                        reservations.reserve_path(agent, &[*from]);
+                    }
+                }
+
+                paths.push(path);
+            }
+
+            return (paths, reservations);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cooperative::ReservationTable;
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::test_utils::portal_honeycomb;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    fn heuristic(a: &HexCell, b: &HexCell) -> usize {
+        a.axial_dist_to(b)
+    }
+
+    /// Checks that no two of the given paths ever occupy the same cell or swap cells
+    fn assert_no_conflicts(honeycomb: &Honeycomb<()>, paths: &[Vec<HexCell>]) {
+        let end = paths.iter().map(|p| p.len()).max().unwrap_or(0) + 1;
+        let at = |path: &Vec<HexCell>, t: usize| path[t.min(path.len() - 1)];
+
+        for (i, a) in paths.iter().enumerate() {
+            for step in a.windows(2) {
+                assert!(step[0].axial_dist_to(&step[1]) <= 1);
+                assert!(honeycomb.data.contains_key(&step[1]));
+            }
+
+            for b in &paths[i + 1..] {
+                for t in 0..end {
+                    assert_ne!(at(a, t), at(b, t));
+                    assert!(at(a, t) != at(b, t + 1) || at(a, t + 1) != at(b, t));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_head_on() {
+        // A corridor with a single passing place, which the agents have to take turns using
+        let mut honeycomb = Honeycomb::<()>::new(3);
+        for q in -3..=3 {
+            honeycomb.data.insert(HexCell::new(q, 0), ());
+        }
+        honeycomb.data.insert(HexCell::new(1, -1), ());
+
+        let agents = [
+            (HexCell::new(-3, 0), HexCell::new(3, 0)),
+            (HexCell::new(3, 0), HexCell::new(-3, 0)),
+        ];
+        let (paths, reservations) =
+            honeycomb.cooperative_paths(&agents, 30, |_, _, _| 1, heuristic);
+        let paths = paths.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+
+        assert_eq!(paths[0].len(), 7);
+        assert!(paths[1].contains(&HexCell::new(1, -1)));
+        assert_no_conflicts(&honeycomb, &paths);
+
+        assert_eq!(reservations.occupant(&HexCell::new(-3, 0), 0), Some(0));
+        assert_eq!(reservations.occupant(&HexCell::new(3, 0), 100), Some(0));
+    }
+
+    #[test]
+    fn test_crowd() {
+        let mut rng = StdRng::seed_from_u64(47);
+        let mut honeycomb = Honeycomb::<()>::new(6);
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, ());
+        }
+
+        let mut starts = honeycomb.grid.clone();
+        let mut goals = honeycomb.grid.clone();
+        starts.shuffle(&mut rng);
+        goals.shuffle(&mut rng);
+
+        let agents = starts.into_iter().zip(goals).take(20).collect::<Vec<_>>();
+        let (paths, _) = honeycomb.cooperative_paths(&agents, 100, |_, _, _| 1, heuristic);
+
+        assert!(paths.iter().flatten().count() >= 18);
+
+        // Agents without a path stay where they start, and nobody runs into them either
+        let paths = paths
+            .into_iter()
+            .zip(&agents)
+            .map(|(path, (from, _))| path.unwrap_or(vec![*from]))
+            .collect::<Vec<_>>();
+        assert_no_conflicts(&honeycomb, &paths);
+    }
+
+    #[test]
+    fn test_stranded() {
+        // A corridor with a detour around its middle cell
+        let mut honeycomb = Honeycomb::<()>::new(2);
+        for hex in [(-1, 0), (0, 0), (1, 0), (0, -1), (1, -1)] {
+            honeycomb.data.insert(HexCell::new(hex.0, hex.1), ());
+        }
+
+        // The second agent can't reach its goal, so the first has to go around it
+        let agents = [
+            (HexCell::new(-1, 0), HexCell::new(1, 0)),
+            (HexCell::origin(), HexCell::new(0, 2)),
+        ];
+        let (paths, reservations) =
+            honeycomb.cooperative_paths(&agents, 10, |_, _, _| 1, heuristic);
+
+        let path = paths[0].clone().unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(paths[1], None);
+        assert_no_conflicts(&honeycomb, &[path, vec![HexCell::origin()]]);
+        assert_eq!(reservations.occupant(&HexCell::origin(), 100), Some(1));
+
+        // Without the detour neither agent gets anywhere
+        honeycomb.data.remove(&HexCell::new(0, -1));
+        let (paths, reservations) =
+            honeycomb.cooperative_paths(&agents, 10, |_, _, _| 1, heuristic);
+
+        assert_eq!(paths, [None, None]);
+        assert_eq!(reservations.occupant(&HexCell::new(-1, 0), 100), Some(0));
+        assert_eq!(reservations.occupant(&HexCell::origin(), 100), Some(1));
+    }
+
+    #[test]
+    fn test_portals() {
+        let honeycomb = portal_honeycomb();
+        let from = HexCell::new(1, 1);
+        let to = HexCell::new(5, 1);
+        let cost_fn = |_: &u8, _: &u8, _: Option<&()>| 1;
+        let mut reservations = ReservationTable::new();
+
+        let through = vec![from, HexCell::new(2, 1), HexCell::new(4, 1), to];
+        let path = honeycomb.cooperative_path(&reservations, &from, &to, 10, cost_fn, heuristic);
+        assert_eq!(path, Some(through));
+        assert_eq!(
+            honeycomb.cooperative_path(&reservations, &to, &from, 10, cost_fn, heuristic),
+            None
+        );
+
+        // An agent standing on the far end of the portal keeps others from stepping through it
+        reservations.reserve_path(0, &[HexCell::new(4, 1)]);
+        let path = honeycomb.cooperative_path(&reservations, &from, &to, 10, cost_fn, heuristic);
+        assert_eq!(path, None);
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{Add, Neg, Sub};
+
+/// An integer type that can be used for the coordinates of a `HexCell`.
+///
+/// This is implemented for `i16`, `i32` and `i64`, trading the range of coordinates for memory.
+/// Arithmetic on coordinates behaves like arithmetic on the integers themselves: `+` and `-`
+/// panic on overflow in debug builds and wrap around in release builds, while the `checked_`
+/// methods return `None` instead.
+pub trait Coord:
+    Copy
+    + Debug
+    + Default
+    + Eq
+    + Ord
+    + Hash
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+
+    /// Returns this coordinate widened to an `i128`, which can hold the result of any arithmetic
+    /// between two coordinates without overflowing
+    fn to_i128(self) -> i128;
+
+    /// Returns the given value if it fits into this type
+    fn from_i128(value: i128) -> Option<Self>;
+
+    fn to_f32(self) -> f32;
+
+    fn to_f64(self) -> f64;
+
+    /// Returns the given value rounded towards zero, saturating at the bounds of this type
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! impl_coord {
+    ($($t:ty),*) => {
+        $(
+            impl Coord for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+
+                fn from_i128(value: i128) -> Option<Self> {
+                    <$t>::try_from(value).ok()
+                }
+
+                fn to_f32(self) -> f32 {
+                    self as f32
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                fn from_f64(value: f64) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_coord!(i16, i32, i64);
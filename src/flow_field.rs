@@ -0,0 +1,255 @@
+use crate::hex_cell::HexCell;
+use crate::honeycomb::{grid_index, Honeycomb};
+use crate::portal::Step;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// The direction of the cheapest step towards the nearest goal from every cell of a honeycomb,
+/// returned by `Honeycomb::flow_field()`. Any number of units can follow the same field, each
+/// looking up the cell it stands on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowField {
+    size: usize,
+    /// The cost of reaching the nearest goal from every cell, in grid order
+    costs: Vec<usize>,
+    /// The index into `HexCell::directions()` of the step to take from every cell, in grid order
+    directions: Vec<Option<u8>>,
+    /// The cell to step into from every cell whose step goes through a portal, keyed by grid index
+    portal_steps: HashMap<usize, HexCell>,
+}
+
+impl FlowField {
+    /// Returns the cost of reaching the nearest goal from the given cell, or `None` if no goal can
+    /// be reached from it
+    pub fn cost_at(&self, hex: &HexCell) -> Option<usize> {
+        let cost = self.costs[grid_index(self.size, hex)?];
+        (cost != usize::MAX).then_some(cost)
+    }
+
+    /// Returns the index into `HexCell::directions()` of the step to take from the given cell, or
+    /// `None` if the cell is a goal, no goal can be reached from it or the step goes through a
+    /// portal
+    pub fn direction_at(&self, hex: &HexCell) -> Option<usize> {
+        self.directions[grid_index(self.size, hex)?].map(usize::from)
+    }
+
+    /// Returns the cell to step into from the given cell, or `None` if the cell is a goal or no
+    /// goal can be reached from it
+    pub fn next_cell(&self, hex: &HexCell) -> Option<HexCell> {
+        let index = grid_index(self.size, hex)?;
+        if let Some(next) = self.portal_steps.get(&index) {
+            return Some(*next);
+        }
+
+        self.directions[index].map(|direction| *hex + HexCell::directions()[direction as usize])
+    }
+}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Computes the flow field towards the given goals: the cost of reaching the nearest goal from
+    /// every cell, and the direction of the first step of the cheapest path there.
+    ///
+    /// [`cost_fn`] is always called with the cells of a step in the direction of travel towards the
+    /// goals, even though the costs are computed backwards from them. Goals outside the honeycomb
+    /// are ignored.
+    ///
+    /// When several steps out of a cell are equally cheap, the one that reaches a goal in the fewest
+    /// steps is taken, so following the field always ends at a goal even where steps cost nothing.
+    /// Remaining ties go to the step that comes first in `HexCell::directions()`, followed by
+    /// portals in the order they were added, so the same honeycomb always yields the same field.
+    pub fn flow_field(
+        &self,
+        goals: &[HexCell],
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+    ) -> FlowField {
+        let mut costs = vec![usize::MAX; self.grid_len()];
+        // The number of steps along the cheapest path to the nearest goal, which breaks ties
+        // between equally cheap paths
+        let mut hops = vec![usize::MAX; self.grid_len()];
+        let mut is_goal = vec![false; self.grid_len()];
+        let mut open = BinaryHeap::new();
+
+        for goal in goals {
+            if let Some(index) = self.index_of(goal) {
+                costs[index] = 0;
+                hops[index] = 0;
+                is_goal[index] = true;
+                open.push(Reverse((0, 0, index)));
+            }
+        }
+
+        // Dijkstra's algorithm from every goal at once, moving against the direction of travel
+        while let Some(Reverse((cost, hop, curr))) = open.pop() {
+            if (cost, hop) > (costs[curr], hops[curr]) {
+                continue;
+            }
+
+            let hex = self.cell_at(curr);
+            let Some(curr_val) = self.data.get(&hex) else {
+                continue;
+            };
+
+            for (neighbor, prev, step) in self.steps(hex, true) {
+                let Some(prev_val) = self.data.get(&neighbor) else {
+                    continue;
+                };
+
+                let step_cost = self.step_cost(prev_val, curr_val, step, cost_fn);
+                let new_cost = cost.saturating_add(step_cost);
+
+                if (new_cost, hop + 1) < (costs[prev], hops[prev]) {
+                    costs[prev] = new_cost;
+                    hops[prev] = hop + 1;
+                    open.push(Reverse((new_cost, hop + 1, prev)));
+                }
+            }
+        }
+
+        // Steps are picked once all costs are final, so they don't depend on the order in which
+        // cells left the work list
+        let mut directions = vec![None; self.grid_len()];
+        let mut portal_steps = HashMap::new();
+
+        for (index, hex) in self.cells().enumerate() {
+            if is_goal[index] || costs[index] == usize::MAX {
+                continue;
+            }
+            let Some(curr_val) = self.data.get(&hex) else {
+                continue;
+            };
+
+            let mut best: Option<((usize, usize), HexCell, Step)> = None;
+            for (neighbor, next, step) in self.steps(hex, false) {
+                let Some(next_val) = self.data.get(&neighbor) else {
+                    continue;
+                };
+                if costs[next] == usize::MAX {
+                    continue;
+                }
+
+                let step_cost = self.step_cost(curr_val, next_val, step, cost_fn);
+                let cost = (step_cost.saturating_add(costs[next]), hops[next]);
+
+                // The cheapest step with the fewest hops always gets one hop closer to a goal,
+                // so no cells step into each other in a cycle
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, neighbor, step));
+                }
+            }
+
+            match best {
+                Some((_, next, Step::Portal(_))) => {
+                    portal_steps.insert(index, next);
+                }
+                Some((_, next, Step::Edge(_))) => {
+                    let offset = next - hex;
+                    directions[index] = HexCell::directions()
+                        .iter()
+                        .position(|d| *d == offset)
+                        .map(|direction| direction as u8);
+                }
+                None => {}
+            }
+        }
+
+        FlowField {
+            size: self.size(),
+            costs,
+            directions,
+            portal_steps,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::test_utils::{path_cost, random_honeycomb};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn cost_fn(a: &u8, b: &u8, _: Option<&()>) -> usize {
+        *a as usize + 2 * *b as usize
+    }
+
+    #[test]
+    fn test_follow_field() {
+        let mut rng = StdRng::seed_from_u64(46);
+        let honeycomb = random_honeycomb(&mut rng, 10, (4, 5), 1..4);
+
+        let goals = [HexCell::new(-3, 8), HexCell::new(6, -2)];
+        let field = honeycomb.flow_field(&goals, cost_fn);
+        let heuristic = |_: &HexCell, _: &HexCell| 0;
+
+        for h in honeycomb.grid.iter() {
+            // The field holds the cost of the cheapest path to the nearest goal
+            let cheapest = goals
+                .iter()
+                .filter_map(|g| honeycomb.cheapest_path(h, g, cost_fn, heuristic))
+                .map(|path| path_cost(&honeycomb, &path, cost_fn))
+                .min();
+            assert_eq!(field.cost_at(h), cheapest);
+
+            // Following the field spends exactly that cost and ends up at a goal
+            let Some(mut cost) = field.cost_at(h) else {
+                assert_eq!(field.direction_at(h), None);
+                continue;
+            };
+
+            let mut curr = *h;
+            while let Some(next) = field.next_cell(&curr) {
+                cost -= cost_fn(&honeycomb.data[&curr], &honeycomb.data[&next], None);
+                curr = next;
+            }
+            assert!(goals.contains(&curr));
+            assert_eq!(cost, 0);
+        }
+    }
+
+    #[test]
+    fn test_ties() {
+        let mut honeycomb = Honeycomb::<u8>::new(3);
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, 1);
+        }
+
+        let field = honeycomb.flow_field(&[HexCell::origin()], |_, _, _| 1);
+        assert_eq!(field.direction_at(&HexCell::origin()), None);
+        assert_eq!(field.direction_at(&HexCell::new(2, 0)), Some(3));
+
+        // Stepping west and north-west is equally cheap, and north-west comes first
+        assert_eq!(field.direction_at(&HexCell::new(1, 1)), Some(2));
+        assert_eq!(
+            field.next_cell(&HexCell::new(1, 1)),
+            Some(HexCell::new(1, 0))
+        );
+        assert_eq!(field.cost_at(&HexCell::new(3, 0)), Some(3));
+        assert_eq!(field.cost_at(&HexCell::new(4, 0)), None);
+    }
+
+    #[test]
+    fn test_zero_cost() {
+        let mut honeycomb = Honeycomb::<u8>::new(3);
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, 1);
+        }
+
+        // Every step is free, so only the number of steps tells the ways to the goal apart
+        let goal = HexCell::origin();
+        let field = honeycomb.flow_field(&[goal], |_, _, _| 0);
+
+        for h in honeycomb.grid.iter() {
+            assert_eq!(field.cost_at(h), Some(0));
+
+            let mut curr = *h;
+            let mut steps = 0;
+            while let Some(next) = field.next_cell(&curr) {
+                assert!(steps < honeycomb.grid.len(), "cycle from {h:?}");
+                curr = next;
+                steps += 1;
+            }
+            assert_eq!((curr, steps), (goal, h.axial_dist_to(&goal)));
+        }
+    }
+}
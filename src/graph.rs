@@ -0,0 +1,680 @@
+//! Implementations of the `petgraph` visitor traits, so that the algorithms of `petgraph` run
+//! directly on a `Honeycomb` without copying it into a `petgraph::Graph`.
+//!
+//! A `Honeycomb` is an undirected graph with a node for every cell of its grid and an edge between
+//! every pair of neighboring cells. Nodes are identified by their `HexCell` and edges by their
+//! `HexEdge`. Use `Honeycomb::weighted()` for algorithms that need edges with weights.
+//!
+//! Portals are one-way, so they are left out of both undirected graphs. Use
+//! `Honeycomb::directed()` for a directed graph that steps through them as well, the same way the
+//! searches of `Honeycomb` do.
+
+use crate::hex_cell::HexCell;
+use crate::hex_edge::HexEdge;
+use crate::honeycomb::{grid_index, Cells, Honeycomb, NeighborsOf};
+use crate::portal::Step;
+use petgraph::visit::{
+    Data, EdgeRef, GraphBase, GraphProp, GraphRef, IntoEdgeReferences, IntoEdges,
+    IntoEdgesDirected, IntoNeighbors, IntoNeighborsDirected, IntoNodeIdentifiers,
+    IntoNodeReferences, NodeCompactIndexable, NodeCount, NodeIndexable, VisitMap, Visitable,
+};
+use petgraph::{Directed, Direction, Undirected};
+
+/// Marks which cells of a honeycomb have been visited by a graph traversal
+#[derive(Clone, Debug)]
+pub struct VisitedCells {
+    size: usize,
+    visited: Vec<bool>,
+}
+
+impl VisitedCells {
+    fn new(size: usize, len: usize) -> Self {
+        Self {
+            size,
+            visited: vec![false; len],
+        }
+    }
+}
+
+impl VisitMap<HexCell> for VisitedCells {
+    fn visit(&mut self, a: HexCell) -> bool {
+        let i = grid_index(self.size, &a).expect("visited a cell outside of the honeycomb");
+
+        !std::mem::replace(&mut self.visited[i], true)
+    }
+
+    fn is_visited(&self, a: &HexCell) -> bool {
+        grid_index(self.size, a).is_some_and(|i| self.visited[i])
+    }
+
+    fn unvisit(&mut self, a: HexCell) -> bool {
+        match grid_index(self.size, &a) {
+            Some(i) => std::mem::replace(&mut self.visited[i], false),
+            None => false,
+        }
+    }
+}
+
+impl<T, E, V> GraphBase for Honeycomb<T, E, V> {
+    type EdgeId = HexEdge;
+    type NodeId = HexCell;
+}
+
+impl<T, E, V> GraphProp for Honeycomb<T, E, V> {
+    type EdgeType = Undirected;
+}
+
+impl<T, E, V> NodeCount for Honeycomb<T, E, V> {
+    fn node_count(&self) -> usize {
+        self.grid_len()
+    }
+}
+
+impl<T, E, V> NodeIndexable for Honeycomb<T, E, V> {
+    fn node_bound(&self) -> usize {
+        self.grid_len()
+    }
+
+    fn to_index(&self, a: HexCell) -> usize {
+        self.index_of(&a)
+            .expect("indexed a cell outside of the honeycomb")
+    }
+
+    fn from_index(&self, i: usize) -> HexCell {
+        self.cell_at(i)
+    }
+}
+
+impl<T, E, V> NodeCompactIndexable for Honeycomb<T, E, V> {}
+
+impl<T, E, V> Visitable for Honeycomb<T, E, V> {
+    type Map = VisitedCells;
+
+    fn visit_map(&self) -> VisitedCells {
+        VisitedCells::new(self.size(), self.grid_len())
+    }
+
+    fn reset_map(&self, map: &mut VisitedCells) {
+        *map = self.visit_map();
+    }
+}
+
+impl<'a, T, E, V> IntoNeighbors for &'a Honeycomb<T, E, V> {
+    type Neighbors = NeighborsOf<'a>;
+
+    fn neighbors(self, a: HexCell) -> Self::Neighbors {
+        self.iter_adjacent_of(a)
+    }
+}
+
+impl<T, E, V> IntoNodeIdentifiers for &Honeycomb<T, E, V> {
+    type NodeIdentifiers = Cells;
+
+    fn node_identifiers(self) -> Self::NodeIdentifiers {
+        self.cells()
+    }
+}
+
+/// A view of a `Honeycomb` as a graph with weighted edges, returned by `Honeycomb::weighted()`.
+///
+/// Only cells with a value are connected, so cells without one are left as isolated nodes.
+pub struct WeightedHoneycomb<'a, T, E, V, W> {
+    pub honeycomb: &'a Honeycomb<T, E, V>,
+    weight_fn: fn(a_val: &T, b_val: &T, edge_val: Option<&E>) -> W,
+}
+
+impl<T, E, V, W> Clone for WeightedHoneycomb<'_, T, E, V, W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, E, V, W> Copy for WeightedHoneycomb<'_, T, E, V, W> {}
+
+/// An edge of a `WeightedHoneycomb`, leading from `source` to `target`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WeightedEdge<W> {
+    pub source: HexCell,
+    pub target: HexCell,
+    pub edge: HexEdge,
+    pub weight: W,
+}
+
+impl<W: Copy> EdgeRef for WeightedEdge<W> {
+    type NodeId = HexCell;
+    type EdgeId = HexEdge;
+    type Weight = W;
+
+    fn source(&self) -> HexCell {
+        self.source
+    }
+
+    fn target(&self) -> HexCell {
+        self.target
+    }
+
+    fn weight(&self) -> &W {
+        &self.weight
+    }
+
+    fn id(&self) -> HexEdge {
+        self.edge
+    }
+}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Returns a view of this honeycomb as a graph whose edges are weighted by [`weight_fn`].
+    ///
+    /// [`weight_fn`] takes in the values of both cells along an edge and the value of the edge
+    /// itself, and returns the weight of the edge. The cells are always passed in the order of
+    /// `HexEdge::cells()`, so an edge has the same weight whichever side it is reached from.
+    pub fn weighted<W: Copy>(
+        &self,
+        weight_fn: fn(a_val: &T, b_val: &T, edge_val: Option<&E>) -> W,
+    ) -> WeightedHoneycomb<'_, T, E, V, W> {
+        WeightedHoneycomb {
+            honeycomb: self,
+            weight_fn,
+        }
+    }
+}
+
+impl<'a, T, E, V, W: Copy> WeightedHoneycomb<'a, T, E, V, W> {
+    /// Returns the given edge leading away from [`source`], if both of its cells have a value
+    fn weighted_edge(&self, source: HexCell, edge: HexEdge) -> Option<WeightedEdge<W>> {
+        let [a, b] = edge.cells();
+        let target = if a == source { b } else { a };
+
+        let honeycomb = self.honeycomb;
+        honeycomb.index_of(&a)?;
+        honeycomb.index_of(&b)?;
+
+        let weight = (self.weight_fn)(
+            honeycomb.data.get(&a)?,
+            honeycomb.data.get(&b)?,
+            honeycomb.edge_data.get(&edge),
+        );
+
+        Some(WeightedEdge {
+            source,
+            target,
+            edge,
+            weight,
+        })
+    }
+}
+
+impl<T, E, V, W> GraphBase for WeightedHoneycomb<'_, T, E, V, W> {
+    type EdgeId = HexEdge;
+    type NodeId = HexCell;
+}
+
+impl<T, E, V, W> GraphRef for WeightedHoneycomb<'_, T, E, V, W> {}
+
+impl<T, E, V, W> GraphProp for WeightedHoneycomb<'_, T, E, V, W> {
+    type EdgeType = Undirected;
+}
+
+impl<T, E, V, W> Data for WeightedHoneycomb<'_, T, E, V, W> {
+    type NodeWeight = ();
+    type EdgeWeight = W;
+}
+
+impl<T, E, V, W> NodeCount for WeightedHoneycomb<'_, T, E, V, W> {
+    fn node_count(&self) -> usize {
+        self.honeycomb.node_count()
+    }
+}
+
+impl<T, E, V, W> NodeIndexable for WeightedHoneycomb<'_, T, E, V, W> {
+    fn node_bound(&self) -> usize {
+        self.honeycomb.node_bound()
+    }
+
+    fn to_index(&self, a: HexCell) -> usize {
+        NodeIndexable::to_index(self.honeycomb, a)
+    }
+
+    fn from_index(&self, i: usize) -> HexCell {
+        NodeIndexable::from_index(self.honeycomb, i)
+    }
+}
+
+impl<T, E, V, W> NodeCompactIndexable for WeightedHoneycomb<'_, T, E, V, W> {}
+
+impl<T, E, V, W> Visitable for WeightedHoneycomb<'_, T, E, V, W> {
+    type Map = VisitedCells;
+
+    fn visit_map(&self) -> VisitedCells {
+        self.honeycomb.visit_map()
+    }
+
+    fn reset_map(&self, map: &mut VisitedCells) {
+        self.honeycomb.reset_map(map)
+    }
+}
+
+impl<'a, T, E, V, W: Copy> IntoNeighbors for WeightedHoneycomb<'a, T, E, V, W> {
+    type Neighbors = std::vec::IntoIter<HexCell>;
+
+    fn neighbors(self, a: HexCell) -> Self::Neighbors {
+        self.edges(a)
+            .map(|e| e.target)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a, T, E, V, W: Copy> IntoEdges for WeightedHoneycomb<'a, T, E, V, W> {
+    type Edges = std::vec::IntoIter<WeightedEdge<W>>;
+
+    fn edges(self, a: HexCell) -> Self::Edges {
+        a.edges()
+            .into_iter()
+            .filter_map(|e| self.weighted_edge(a, e))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a, T, E, V, W: Copy> IntoEdgeReferences for WeightedHoneycomb<'a, T, E, V, W> {
+    type EdgeRef = WeightedEdge<W>;
+    type EdgeReferences = std::vec::IntoIter<WeightedEdge<W>>;
+
+    fn edge_references(self) -> Self::EdgeReferences {
+        self.honeycomb
+            .edges()
+            .into_iter()
+            .filter_map(|e| self.weighted_edge(e.cells()[0], e))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a, T, E, V, W> IntoNodeIdentifiers for WeightedHoneycomb<'a, T, E, V, W> {
+    type NodeIdentifiers = Cells;
+
+    fn node_identifiers(self) -> Self::NodeIdentifiers {
+        self.honeycomb.cells()
+    }
+}
+
+impl<'a, T, E, V, W> IntoNodeReferences for WeightedHoneycomb<'a, T, E, V, W> {
+    type NodeRef = (HexCell, ());
+    type NodeReferences = std::iter::Map<Cells, fn(HexCell) -> (HexCell, ())>;
+
+    fn node_references(self) -> Self::NodeReferences {
+        self.honeycomb.cells().map(|h| (h, ()))
+    }
+}
+
+/// A view of a `Honeycomb` as a directed graph whose edges are the steps of its searches, returned
+/// by `Honeycomb::directed()`. Every pair of neighboring cells is joined by an edge each way, and
+/// every portal by an edge in the direction it leads.
+///
+/// Only cells with a value are connected, so cells without one are left as isolated nodes.
+pub struct DirectedHoneycomb<'a, T, E, V> {
+    pub honeycomb: &'a Honeycomb<T, E, V>,
+    cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+}
+
+impl<T, E, V> Clone for DirectedHoneycomb<'_, T, E, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, E, V> Copy for DirectedHoneycomb<'_, T, E, V> {}
+
+/// Identifies an edge of a `DirectedHoneycomb` by the cells it leads from and to, and whether it
+/// goes through a portal rather than across the edge between neighboring cells
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StepId {
+    pub from: HexCell,
+    pub to: HexCell,
+    pub portal: bool,
+}
+
+/// An edge of a `DirectedHoneycomb`, weighted by the cost of the step it stands for
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DirectedEdge {
+    pub id: StepId,
+    pub cost: usize,
+}
+
+impl EdgeRef for DirectedEdge {
+    type NodeId = HexCell;
+    type EdgeId = StepId;
+    type Weight = usize;
+
+    fn source(&self) -> HexCell {
+        self.id.from
+    }
+
+    fn target(&self) -> HexCell {
+        self.id.to
+    }
+
+    fn weight(&self) -> &usize {
+        &self.cost
+    }
+
+    fn id(&self) -> StepId {
+        self.id
+    }
+}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Returns a view of this honeycomb as a directed graph that includes its portals, with edges
+    /// weighted by what stepping along them costs.
+    ///
+    /// [`cost_fn`] is the one passed to the searches of `Honeycomb`, and is called in the direction
+    /// of every edge. Portals with a cost of their own are weighted by that cost instead.
+    pub fn directed(
+        &self,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+    ) -> DirectedHoneycomb<'_, T, E, V> {
+        DirectedHoneycomb {
+            honeycomb: self,
+            cost_fn,
+        }
+    }
+}
+
+impl<T, E, V> DirectedHoneycomb<'_, T, E, V> {
+    /// Returns the edges leading out of [`hex`], or into it if [`reverse`]
+    fn steps(&self, hex: HexCell, reverse: bool) -> std::vec::IntoIter<DirectedEdge> {
+        let honeycomb = self.honeycomb;
+        let Some(val) = honeycomb.data.get(&hex) else {
+            return Vec::new().into_iter();
+        };
+
+        honeycomb
+            .steps(hex, reverse)
+            .filter_map(|(other, _, step)| {
+                let other_val = honeycomb.data.get(&other)?;
+                let (from, to, cost) = if reverse {
+                    (
+                        other,
+                        hex,
+                        honeycomb.step_cost(other_val, val, step, self.cost_fn),
+                    )
+                } else {
+                    (
+                        hex,
+                        other,
+                        honeycomb.step_cost(val, other_val, step, self.cost_fn),
+                    )
+                };
+                let portal = matches!(step, Step::Portal(_));
+
+                Some(DirectedEdge {
+                    id: StepId { from, to, portal },
+                    cost,
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<T, E, V> GraphBase for DirectedHoneycomb<'_, T, E, V> {
+    type EdgeId = StepId;
+    type NodeId = HexCell;
+}
+
+impl<T, E, V> GraphRef for DirectedHoneycomb<'_, T, E, V> {}
+
+impl<T, E, V> GraphProp for DirectedHoneycomb<'_, T, E, V> {
+    type EdgeType = Directed;
+}
+
+impl<T, E, V> Data for DirectedHoneycomb<'_, T, E, V> {
+    type NodeWeight = ();
+    type EdgeWeight = usize;
+}
+
+impl<T, E, V> NodeCount for DirectedHoneycomb<'_, T, E, V> {
+    fn node_count(&self) -> usize {
+        self.honeycomb.node_count()
+    }
+}
+
+impl<T, E, V> NodeIndexable for DirectedHoneycomb<'_, T, E, V> {
+    fn node_bound(&self) -> usize {
+        self.honeycomb.node_bound()
+    }
+
+    fn to_index(&self, a: HexCell) -> usize {
+        NodeIndexable::to_index(self.honeycomb, a)
+    }
+
+    fn from_index(&self, i: usize) -> HexCell {
+        NodeIndexable::from_index(self.honeycomb, i)
+    }
+}
+
+impl<T, E, V> NodeCompactIndexable for DirectedHoneycomb<'_, T, E, V> {}
+
+impl<T, E, V> Visitable for DirectedHoneycomb<'_, T, E, V> {
+    type Map = VisitedCells;
+
+    fn visit_map(&self) -> VisitedCells {
+        self.honeycomb.visit_map()
+    }
+
+    fn reset_map(&self, map: &mut VisitedCells) {
+        self.honeycomb.reset_map(map)
+    }
+}
+
+impl<T, E, V> IntoNeighbors for DirectedHoneycomb<'_, T, E, V> {
+    type Neighbors = std::vec::IntoIter<HexCell>;
+
+    fn neighbors(self, a: HexCell) -> Self::Neighbors {
+        self.neighbors_directed(a, Direction::Outgoing)
+    }
+}
+
+impl<T, E, V> IntoNeighborsDirected for DirectedHoneycomb<'_, T, E, V> {
+    type NeighborsDirected = std::vec::IntoIter<HexCell>;
+
+    fn neighbors_directed(self, a: HexCell, d: Direction) -> Self::NeighborsDirected {
+        self.edges_directed(a, d)
+            .map(|e| match d {
+                Direction::Outgoing => e.target(),
+                Direction::Incoming => e.source(),
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<T, E, V> IntoEdges for DirectedHoneycomb<'_, T, E, V> {
+    type Edges = std::vec::IntoIter<DirectedEdge>;
+
+    fn edges(self, a: HexCell) -> Self::Edges {
+        self.steps(a, false)
+    }
+}
+
+impl<T, E, V> IntoEdgesDirected for DirectedHoneycomb<'_, T, E, V> {
+    type EdgesDirected = std::vec::IntoIter<DirectedEdge>;
+
+    fn edges_directed(self, a: HexCell, dir: Direction) -> Self::EdgesDirected {
+        self.steps(a, dir == Direction::Incoming)
+    }
+}
+
+impl<T, E, V> IntoEdgeReferences for DirectedHoneycomb<'_, T, E, V> {
+    type EdgeRef = DirectedEdge;
+    type EdgeReferences = std::vec::IntoIter<DirectedEdge>;
+
+    fn edge_references(self) -> Self::EdgeReferences {
+        self.honeycomb
+            .cells()
+            .flat_map(|h| self.steps(h, false))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<T, E, V> IntoNodeIdentifiers for DirectedHoneycomb<'_, T, E, V> {
+    type NodeIdentifiers = Cells;
+
+    fn node_identifiers(self) -> Self::NodeIdentifiers {
+        self.honeycomb.cells()
+    }
+}
+
+impl<T, E, V> IntoNodeReferences for DirectedHoneycomb<'_, T, E, V> {
+    type NodeRef = (HexCell, ());
+    type NodeReferences = std::iter::Map<Cells, fn(HexCell) -> (HexCell, ())>;
+
+    fn node_references(self) -> Self::NodeReferences {
+        self.honeycomb.cells().map(|h| (h, ()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::hex_edge::HexEdge;
+    use crate::honeycomb::Honeycomb;
+    use crate::portal::Portal;
+    use crate::test_utils::portal_honeycomb;
+    use petgraph::algo::{connected_components, dijkstra, min_spanning_tree};
+    use petgraph::data::Element;
+    use petgraph::visit::{
+        Bfs, EdgeRef, IntoEdgeReferences, IntoEdges, IntoEdgesDirected, IntoNeighbors,
+        IntoNeighborsDirected, NodeIndexable,
+    };
+    use petgraph::Direction;
+
+    #[test]
+    fn test_traversal() {
+        let honeycomb = Honeycomb::<()>::new(3);
+
+        for (i, h) in honeycomb.grid.iter().enumerate() {
+            assert_eq!(honeycomb.to_index(*h), i);
+            assert_eq!(honeycomb.from_index(i), *h);
+        }
+
+        // A breadth-first search from the center reaches every cell, ring by ring
+        let mut bfs = Bfs::new(&honeycomb, HexCell::origin());
+        let mut order = Vec::new();
+        while let Some(h) = bfs.next(&honeycomb) {
+            order.push(h);
+        }
+
+        assert_eq!(order.len(), honeycomb.grid.len());
+        assert!(order
+            .windows(2)
+            .all(|w| w[0].axial_dist_to(&HexCell::origin())
+                <= w[1].axial_dist_to(&HexCell::origin())));
+    }
+
+    #[test]
+    fn test_weighted() {
+        // Two islands separated by a column of cells without values
+        let mut honeycomb = Honeycomb::<u32, u32>::new(3);
+        for h in honeycomb.grid.clone() {
+            if h.q != 0 {
+                honeycomb
+                    .data
+                    .insert(h, (h.q.unsigned_abs() + h.r.unsigned_abs()) % 3 + 1);
+            }
+        }
+        let east = HexCell::new(1, 0);
+        let edge = HexEdge::between(&east, &HexCell::new(2, 0)).unwrap();
+        honeycomb.edge_data.insert(edge, 100);
+
+        let weighted = honeycomb.weighted(|a, b, e| a + b + e.copied().unwrap_or(0));
+
+        let empty = honeycomb.grid.iter().filter(|h| h.q == 0).count();
+        assert_eq!(connected_components(weighted), 2 + empty);
+
+        // Edges have the same weight from either side
+        for e in weighted.edges(east) {
+            let back = weighted
+                .edges(e.target())
+                .find(|b| b.id() == e.id())
+                .unwrap();
+            assert_eq!(back.weight, e.weight);
+            assert_eq!(back.target(), east);
+        }
+
+        let costs = dijkstra(weighted, east, None, |e| *e.weight());
+        assert_eq!(
+            costs.len(),
+            honeycomb.grid.iter().filter(|h| h.q > 0).count()
+        );
+        assert!(costs[&HexCell::new(2, 0)] < 100);
+
+        let tree = min_spanning_tree(weighted)
+            .filter(|e| matches!(e, Element::Edge { .. }))
+            .count();
+        assert_eq!(tree, honeycomb.data.len() - 2);
+    }
+
+    #[test]
+    fn test_portals() {
+        let mut honeycomb = Honeycomb::<u32>::new(3);
+        for h in honeycomb.grid.clone() {
+            if h.q != 0 {
+                honeycomb.data.insert(h, 1);
+            }
+        }
+        let from = HexCell::new(-1, 0);
+        let to = HexCell::new(1, 0);
+        assert!(honeycomb.add_portal(Portal::new(from, to, Some(1))));
+
+        // Both undirected graphs agree on leaving the portal out
+        let neighbors = IntoNeighbors::neighbors(&honeycomb, from).collect::<Vec<_>>();
+        assert_eq!(neighbors, from.neighbor_array());
+
+        let weighted = honeycomb.weighted(|a, b, _| a + b);
+        assert!(!dijkstra(weighted, from, None, |e| *e.weight()).contains_key(&to));
+        assert!(weighted
+            .neighbors(from)
+            .all(|h| h.axial_dist_to(&from) == 1));
+    }
+
+    #[test]
+    fn test_directed() {
+        let honeycomb = portal_honeycomb();
+        let cost_fn = |a: &u8, b: &u8, _: Option<&()>| *a as usize + 2 * *b as usize;
+        let directed = honeycomb.directed(cost_fn);
+        let from = HexCell::new(1, 1);
+        let to = HexCell::new(5, 1);
+
+        // The directed graph steps through the portal like the searches of the honeycomb do
+        let costs = dijkstra(directed, from, None, |e| *e.weight());
+        let cheapest = honeycomb
+            .cheapest_path(&from, &to, cost_fn, |_, _| 0)
+            .unwrap();
+        assert_eq!(costs[&to], 3 * (cheapest.len() - 1));
+        assert_eq!(costs.len(), honeycomb.data.len());
+
+        assert!(!dijkstra(directed, to, None, |e| *e.weight()).contains_key(&from));
+
+        let portal = HexCell::new(2, 1);
+        let through = HexCell::new(4, 1);
+        assert!(directed.neighbors(portal).any(|h| h == through));
+        assert!(!directed.neighbors(through).any(|h| h == portal));
+        assert!(directed
+            .neighbors_directed(through, Direction::Incoming)
+            .any(|h| h == portal));
+
+        // Every edge is found from both of its ends
+        for e in directed.edge_references() {
+            assert!(directed
+                .edges_directed(e.target(), Direction::Incoming)
+                .any(|back| back == e));
+        }
+        assert_eq!(
+            directed.edge_references().filter(|e| e.id().portal).count(),
+            1
+        );
+    }
+}
@@ -1,84 +1,166 @@
-use crate::cartesian_point::CartesianPoint;
+use crate::cartesian_point::{CartesianPoint, Float};
+use crate::coord::Coord;
+use crate::hex_edge::HexEdge;
+use crate::hex_vertex::HexVertex;
 use std::cmp::Ordering;
+use std::num::TryFromIntError;
 use std::ops::{Add, Sub};
 
+/// A hex in axial coordinates, with any `Coord` as the type of its coordinates. `HexCell` uses
+/// `i32`, while `HexCell16` saves memory and `HexCell64` covers very large maps.
+///
+/// Only the cells themselves are generic: `Honeycomb`, `HexEdge`, `HexVertex` and the pathfinders
+/// all work on `HexCell`. Cells of other widths are meant for storing and exchanging coordinates,
+/// and are converted to and from `HexCell` with `From` and `TryFrom` where they meet a honeycomb.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub struct HexCell {
-    pub q: i32,
-    pub r: i32,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hex<C = i32> {
+    pub q: C,
+    pub r: C,
 }
 
-impl HexCell {
-    pub fn new(q: i32, r: i32) -> Self {
+pub type HexCell = Hex<i32>;
+pub type HexCell16 = Hex<i16>;
+pub type HexCell64 = Hex<i64>;
+
+impl<C: Coord> Hex<C> {
+    pub fn new(q: C, r: C) -> Self {
         Self { q, r }
     }
 
     pub fn origin() -> Self {
-        Self::new(0, 0)
+        Self::new(C::ZERO, C::ZERO)
     }
 
     pub fn directions() -> [Self; 6] {
+        let (zero, one) = (C::ZERO, C::ONE);
+
         [
-            HexCell::new(1, 0),
-            HexCell::new(1, -1),
-            HexCell::new(0, -1),
-            HexCell::new(-1, 0),
-            HexCell::new(-1, 1),
-            HexCell::new(0, 1),
+            Hex::new(one, zero),
+            Hex::new(one, -one),
+            Hex::new(zero, -one),
+            Hex::new(-one, zero),
+            Hex::new(-one, one),
+            Hex::new(zero, one),
         ]
     }
 
-    pub fn neighbors(&self) -> Vec<HexCell> {
-        Self::directions()
-            .into_iter()
-            .map(|d| &d + self)
-            .collect::<Vec<_>>()
+    /// Returns the six neighbors of this cell, in the same order as `HexCell::directions()`. Like
+    /// `+`, this overflows for cells at the limits of the coordinate type; use
+    /// `HexCell::checked_add()` with `HexCell::directions()` to handle those.
+    pub fn neighbors(&self) -> Vec<Hex<C>> {
+        self.neighbor_array().to_vec()
+    }
+
+    /// Same as `HexCell::neighbors()`, without allocating
+    pub fn neighbor_array(&self) -> [Hex<C>; 6] {
+        Self::directions().map(|d| &d + self)
+    }
+
+    /// Returns the sum of both cells, or `None` if either coordinate overflows
+    pub fn checked_add(&self, rhs: &Hex<C>) -> Option<Hex<C>> {
+        Some(Hex::new(
+            self.q.checked_add(rhs.q)?,
+            self.r.checked_add(rhs.r)?,
+        ))
+    }
+
+    /// Returns the difference of both cells, or `None` if either coordinate overflows
+    pub fn checked_sub(&self, rhs: &Hex<C>) -> Option<Hex<C>> {
+        Some(Hex::new(
+            self.q.checked_sub(rhs.q)?,
+            self.r.checked_sub(rhs.r)?,
+        ))
     }
 
     pub fn world_location(&self) -> CartesianPoint {
-        let y = -1.5 * self.r as f32;
-        let x = 3f32.sqrt() * (self.r as f32 / 2.0 + self.q as f32);
+        self.world_point()
+    }
+
+    /// Same as `HexCell::world_location()`, with coordinates of any `Float` type. Use `f64` to
+    /// keep cells far from the origin apart.
+    pub fn world_point<F: Float>(&self) -> CartesianPoint<F> {
+        let q = F::from_f64(self.q.to_f64());
+        let r = F::from_f64(self.r.to_f64());
+        let half = F::from_f64(0.5);
+
+        let y = -F::from_f64(1.5) * r;
+        let x = F::from_f64(3f64.sqrt()) * (r * half + q);
 
         CartesianPoint::new(x, y)
     }
 
+    /// Returns the hex on which the given world location lies. This is the inverse of
+    /// `HexCell::world_location()`.
+    pub fn from_world<F: Float>(point: CartesianPoint<F>) -> Self {
+        let third = F::from_f64(1.0 / 3.0);
+
+        let frac_q = F::from_f64(3f64.sqrt() / 3.0) * point.x + third * point.y;
+        let frac_r = -F::from_f64(2.0 / 3.0) * point.y;
+        let frac_s = -frac_q - frac_r;
+
+        let q = frac_q.round();
+        let r = frac_r.round();
+        let s = frac_s.round();
+
+        let dq = (q - frac_q).abs();
+        let dr = (r - frac_r).abs();
+        let ds = (s - frac_s).abs();
+
+        let (q, r) = if dq > dr && dq > ds {
+            (-r - s, r)
+        } else if dr > ds {
+            (q, -q - s)
+        } else {
+            (q, r)
+        };
+
+        Hex::new(C::from_f64(q.to_f64()), C::from_f64(r.to_f64()))
+    }
+
     pub fn vertex_locations(&self) -> Vec<CartesianPoint> {
-        let center = self.world_location();
+        self.vertex_points()
+    }
+
+    /// Same as `HexCell::vertex_locations()`, with coordinates of any `Float` type
+    pub fn vertex_points<F: Float>(&self) -> Vec<CartesianPoint<F>> {
+        let center = self.world_point::<F>();
         let (x, y) = (center.x, center.y);
-        let cos30 = 3f32.sqrt() / 2.0;
+        let cos30 = F::from_f64(3f64.sqrt() / 2.0);
+        let (half, one) = (F::from_f64(0.5), F::ONE);
 
         [
-            (x, y + 1.0),
-            (x + cos30, y + 0.5),
-            (x + cos30, y - 0.5),
-            (x, y - 1.0),
-            (x - cos30, y - 0.5),
-            (x - cos30, y + 0.5),
+            (x, y + one),
+            (x + cos30, y + half),
+            (x + cos30, y - half),
+            (x, y - one),
+            (x - cos30, y - half),
+            (x - cos30, y + half),
         ]
         .map(|(x, y)| CartesianPoint::new(x, y))
         .to_vec()
     }
 
-    pub fn axial_dist_to(&self, to: &HexCell) -> usize {
-        let vec = self - to;
+    /// Returns the number of steps between both cells. This never overflows, even between cells at
+    /// opposite limits of the coordinate type, but saturates at `usize::MAX` on targets where the
+    /// distance doesn't fit.
+    pub fn axial_dist_to(&self, to: &Hex<C>) -> usize {
+        let q = self.q.to_i128() - to.q.to_i128();
+        let r = self.r.to_i128() - to.r.to_i128();
 
-        (vec.q.abs() + vec.r.abs() + (-vec.q - vec.r).abs()) as usize / 2
+        usize::try_from((q.abs() + r.abs() + (q + r).abs()) / 2).unwrap_or(usize::MAX)
     }
 
-    pub fn shortest_path(&self, to: &HexCell, visitable: Vec<&HexCell>) -> Option<Vec<HexCell>> {
+    pub fn shortest_path(&self, to: &Hex<C>, visitable: Vec<&Hex<C>>) -> Option<Vec<Hex<C>>> {
         if self == to {
             return Some(vec![*self]);
         }
 
         let visitable = visitable
             .into_iter()
-            .filter(|h| {
-                println!("{:?}, {:?}, {:?}", h, self, *h != self);
-                *h != self
-            })
+            .filter(|h| *h != self)
             .collect::<Vec<_>>();
 
-        println!("Second: {}", visitable.len());
         let shortest_path = self
             .neighbors()
             .into_iter()
@@ -99,7 +181,19 @@ impl HexCell {
     }
 }
 
-impl Add for HexCell {
+impl HexCell {
+    /// Returns the six edges of this cell, in the same order as `HexCell::directions()`
+    pub fn edges(&self) -> [HexEdge; 6] {
+        [0, 1, 2, 3, 4, 5].map(|i| HexEdge::from_direction(*self, i))
+    }
+
+    /// Returns the six vertices of this cell, in the same order as `HexCell::vertex_locations()`
+    pub fn vertices(&self) -> [HexVertex; 6] {
+        [0, 1, 2, 3, 4, 5].map(|i| HexVertex::from_corner(*self, i))
+    }
+}
+
+impl<C: Coord> Add for Hex<C> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
@@ -110,18 +204,18 @@ impl Add for HexCell {
     }
 }
 
-impl Add for &HexCell {
-    type Output = HexCell;
+impl<C: Coord> Add for &Hex<C> {
+    type Output = Hex<C>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        HexCell {
+        Hex {
             q: self.q + rhs.q,
             r: self.r + rhs.r,
         }
     }
 }
 
-impl Sub for HexCell {
+impl<C: Coord> Sub for Hex<C> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -132,21 +226,51 @@ impl Sub for HexCell {
     }
 }
 
-impl Sub for &HexCell {
-    type Output = HexCell;
+impl<C: Coord> Sub for &Hex<C> {
+    type Output = Hex<C>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        HexCell {
+        Hex {
             q: self.q - rhs.q,
             r: self.r - rhs.r,
         }
     }
 }
 
+macro_rules! impl_widening {
+    ($($from:ty => $to:ty),*) => {
+        $(
+            impl From<Hex<$from>> for Hex<$to> {
+                fn from(value: Hex<$from>) -> Self {
+                    Hex::new(value.q.into(), value.r.into())
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_narrowing {
+    ($($from:ty => $to:ty),*) => {
+        $(
+            impl TryFrom<Hex<$from>> for Hex<$to> {
+                type Error = TryFromIntError;
+
+                fn try_from(value: Hex<$from>) -> Result<Self, Self::Error> {
+                    Ok(Hex::new(value.q.try_into()?, value.r.try_into()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_widening!(i16 => i32, i16 => i64, i32 => i64);
+impl_narrowing!(i32 => i16, i64 => i16, i64 => i32);
+
 #[cfg(test)]
 mod tests {
     use crate::cartesian_point::CartesianPoint;
-    use crate::hex_cell::HexCell;
+    use crate::coord::Coord;
+    use crate::hex_cell::{Hex, HexCell, HexCell16, HexCell64};
 
     #[test]
     fn test_add() {
@@ -187,6 +311,8 @@ mod tests {
         assert!(neighbors.contains(&HexCell::new(4, 5)));
         assert!(neighbors.contains(&HexCell::new(4, 6)));
         assert!(neighbors.contains(&HexCell::new(5, 6)));
+
+        assert_eq!(h.neighbor_array().to_vec(), neighbors);
     }
 
     #[test]
@@ -200,4 +326,122 @@ mod tests {
         let y2 = HexCell::new(-2, 4);
         assert_eq!(y2.world_location(), CartesianPoint::new(0.0, -6.0));
     }
+
+    #[test]
+    fn test_from_world() {
+        for q in -5..=5 {
+            for r in -5..=5 {
+                let h = HexCell::new(q, r);
+                assert_eq!(HexCell::from_world(h.world_location()), h);
+
+                // Every point well inside the hexagon belongs to the same cell
+                for v in h.vertex_locations() {
+                    let c = h.world_location();
+                    let p = CartesianPoint::new(c.x + (v.x - c.x) * 0.9, c.y + (v.y - c.y) * 0.9);
+                    assert_eq!(HexCell::from_world(p), h);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_far_from_origin() {
+        let mut f32_misses = 0;
+
+        for (q, r) in [
+            (1_000_000, 0),
+            (-1_000_000, 0),
+            (0, 1_000_000),
+            (0, -1_000_000),
+            (1_000_000, -1_000_000),
+            (-1_000_000, 1_000_000),
+        ] {
+            for h in HexCell::new(q, r).neighbors() {
+                let c = h.world_point::<f64>();
+
+                for v in h.vertex_points::<f64>() {
+                    let p = c.lerp(&v, 0.95);
+                    assert_eq!(HexCell::from_world(p), h);
+
+                    if HexCell::from_world(CartesianPoint::new(p.x as f32, p.y as f32)) != h {
+                        f32_misses += 1;
+                    }
+                }
+            }
+        }
+
+        // The same points rounded to f32 are too coarse to be picked reliably
+        assert!(f32_misses > 0);
+    }
+
+    fn check_width<C: Coord>() {
+        let h = Hex::<C>::new(C::ONE, -C::ONE);
+        let neighbors = h.neighbors();
+
+        assert_eq!(neighbors.len(), 6);
+        for n in neighbors {
+            assert_eq!(h.axial_dist_to(&n), 1);
+            assert_eq!(Hex::<C>::from_world(n.world_location()), n);
+        }
+
+        let (min, max) = (Hex::new(C::MIN, C::MIN), Hex::new(C::MAX, C::MAX));
+        let corner = Hex::new(C::MAX, C::ZERO);
+
+        assert_eq!(max.checked_add(&Hex::origin()), Some(max));
+        assert_eq!(max.checked_add(&Hex::directions()[0]), None);
+        assert_eq!(max.checked_add(&Hex::directions()[1]), None);
+        assert_eq!(min.checked_sub(&Hex::directions()[0]), None);
+        assert_eq!(
+            min.checked_add(&Hex::directions()[0]),
+            Some(Hex::new(C::MIN + C::ONE, C::MIN))
+        );
+        assert_eq!(
+            corner.checked_add(&Hex::directions()[2]),
+            Some(Hex::new(C::MAX, -C::ONE))
+        );
+
+        // The distance between opposite corners doesn't fit into the coordinate type, and for i64
+        // not even into usize
+        let span = C::MAX.to_i128() - C::MIN.to_i128();
+        let far = usize::try_from(span * 2).unwrap_or(usize::MAX);
+        assert_eq!(min.axial_dist_to(&max), far);
+        assert_eq!(max.axial_dist_to(&min), far);
+        assert_eq!(
+            Hex::new(C::MIN, C::MAX).axial_dist_to(&Hex::new(C::MAX, C::MIN)),
+            span as usize
+        );
+    }
+
+    #[test]
+    fn test_widths() {
+        check_width::<i16>();
+        check_width::<i32>();
+        check_width::<i64>();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_add_overflow() {
+        let _ = HexCell16::new(i16::MAX, 0) + HexCell16::directions()[0];
+    }
+
+    #[test]
+    fn test_conversions() {
+        let small = HexCell16::new(i16::MIN, i16::MAX);
+        let wide = HexCell64::from(small);
+        assert_eq!(wide, HexCell64::new(i16::MIN as i64, i16::MAX as i64));
+        assert_eq!(
+            HexCell::from(small),
+            HexCell::new(i16::MIN as i32, i16::MAX as i32)
+        );
+
+        assert_eq!(HexCell16::try_from(wide), Ok(small));
+        assert_eq!(
+            HexCell::try_from(HexCell64::new(-5, 7)),
+            Ok(HexCell::new(-5, 7))
+        );
+        assert!(HexCell16::try_from(HexCell::new(i16::MAX as i32 + 1, 0)).is_err());
+        assert!(HexCell::try_from(HexCell64::new(0, i64::MIN)).is_err());
+    }
 }
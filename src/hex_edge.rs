@@ -0,0 +1,147 @@
+use crate::cartesian_point::CartesianPoint;
+use crate::hex_cell::HexCell;
+use crate::hex_vertex::HexVertex;
+
+/// The three edges owned by each cell. The remaining three edges of a cell are owned by its
+/// western, south western and south eastern neighbors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EdgeDirection {
+    East,
+    NorthEast,
+    NorthWest,
+}
+
+impl EdgeDirection {
+    /// Returns the index of this direction in `HexCell::directions()`
+    pub fn index(&self) -> usize {
+        match self {
+            EdgeDirection::East => 0,
+            EdgeDirection::NorthEast => 1,
+            EdgeDirection::NorthWest => 2,
+        }
+    }
+}
+
+/// The edge between two adjacent cells.
+///
+/// Every edge is owned by exactly one of the two cells it separates, so each edge has exactly one
+/// representation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexEdge {
+    pub cell: HexCell,
+    pub direction: EdgeDirection,
+}
+
+impl HexEdge {
+    pub fn new(cell: HexCell, direction: EdgeDirection) -> Self {
+        Self { cell, direction }
+    }
+
+    /// Returns the edge of `cell` facing the given direction, where `direction` is an index into
+    /// `HexCell::directions()`
+    pub fn from_direction(cell: HexCell, direction: usize) -> Self {
+        match direction % 6 {
+            0 => Self::new(cell, EdgeDirection::East),
+            1 => Self::new(cell, EdgeDirection::NorthEast),
+            2 => Self::new(cell, EdgeDirection::NorthWest),
+            i => Self::from_direction(cell + HexCell::directions()[i], i - 3),
+        }
+    }
+
+    /// Returns the edge separating `a` and `b`, or `None` if the cells are not adjacent
+    pub fn between(a: &HexCell, b: &HexCell) -> Option<Self> {
+        let offset = b - a;
+
+        HexCell::directions()
+            .iter()
+            .position(|d| *d == offset)
+            .map(|i| Self::from_direction(*a, i))
+    }
+
+    /// Returns the two cells separated by this edge
+    pub fn cells(&self) -> [HexCell; 2] {
+        [
+            self.cell,
+            self.cell + HexCell::directions()[self.direction.index()],
+        ]
+    }
+
+    /// Returns the two vertices at the ends of this edge
+    pub fn vertices(&self) -> [HexVertex; 2] {
+        // The edge facing direction `i` runs between corners `7 - i` and `8 - i` of the owning cell
+        let corner = (7 - self.direction.index()) % 6;
+
+        [
+            HexVertex::from_corner(self.cell, corner),
+            HexVertex::from_corner(self.cell, (corner + 1) % 6),
+        ]
+    }
+
+    /// Returns the world location of the midpoint of this edge
+    pub fn world_location(&self) -> CartesianPoint {
+        let [a, b] = self.cells().map(|h| h.world_location());
+
+        CartesianPoint::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0)
+    }
+
+    /// Returns the world locations of the two ends of this edge
+    pub fn vertex_locations(&self) -> [CartesianPoint; 2] {
+        self.vertices().map(|v| v.world_location())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::hex_edge::{EdgeDirection, HexEdge};
+
+    #[test]
+    fn test_canonical() {
+        let h = HexCell::new(2, -1);
+
+        for (i, d) in HexCell::directions().into_iter().enumerate() {
+            let neighbor = h + d;
+            let edge = HexEdge::from_direction(h, i);
+
+            assert_eq!(edge, HexEdge::from_direction(neighbor, (i + 3) % 6));
+            assert_eq!(HexEdge::between(&h, &neighbor), Some(edge));
+            assert!(edge.cells().contains(&h));
+            assert!(edge.cells().contains(&neighbor));
+        }
+
+        assert_eq!(
+            HexEdge::from_direction(h, 3),
+            HexEdge::new(HexCell::new(1, -1), EdgeDirection::East)
+        );
+        assert_eq!(HexEdge::between(&h, &HexCell::new(4, -1)), None);
+    }
+
+    #[test]
+    fn test_vertices() {
+        let h = HexCell::new(-3, 1);
+        let corners = h.vertex_locations();
+
+        for (i, edge) in h.edges().into_iter().enumerate() {
+            let [a, b] = edge.vertex_locations();
+            let center = h.world_location();
+            let direction = HexCell::directions()[i].world_location();
+
+            // Both ends of the edge are corners of the cell
+            assert!(corners
+                .iter()
+                .any(|c| (c.x - a.x).abs() < 1e-4 && (c.y - a.y).abs() < 1e-4));
+            assert!(corners
+                .iter()
+                .any(|c| (c.x - b.x).abs() < 1e-4 && (c.y - b.y).abs() < 1e-4));
+
+            // The midpoint of the edge lies halfway towards the neighbor
+            let mid = edge.world_location();
+            assert!((mid.x - (center.x + direction.x / 2.0)).abs() < 1e-4);
+            assert!((mid.y - (center.y + direction.y / 2.0)).abs() < 1e-4);
+            assert!(((a.x + b.x) / 2.0 - mid.x).abs() < 1e-4);
+            assert!(((a.y + b.y) / 2.0 - mid.y).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::cartesian_point::CartesianPoint;
+use crate::hex_cell::HexCell;
+use crate::hex_edge::{EdgeDirection, HexEdge};
+
+/// The two vertices owned by each cell. The remaining four vertices of a cell are owned by its
+/// diagonal neighbors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VertexDirection {
+    North,
+    South,
+}
+
+/// A vertex where three cells meet.
+///
+/// Every vertex is owned by exactly one of the three cells that share it, so each vertex has exactly
+/// one representation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexVertex {
+    pub cell: HexCell,
+    pub direction: VertexDirection,
+}
+
+impl HexVertex {
+    pub fn new(cell: HexCell, direction: VertexDirection) -> Self {
+        Self { cell, direction }
+    }
+
+    /// Returns the given corner of `cell`, where `corner` is an index into
+    /// `HexCell::vertex_locations()`
+    pub fn from_corner(cell: HexCell, corner: usize) -> Self {
+        let d = HexCell::directions();
+
+        match corner % 6 {
+            0 => Self::new(cell, VertexDirection::North),
+            1 => Self::new(cell + d[1], VertexDirection::South),
+            2 => Self::new(cell + d[5], VertexDirection::North),
+            3 => Self::new(cell, VertexDirection::South),
+            4 => Self::new(cell + d[4], VertexDirection::North),
+            _ => Self::new(cell + d[2], VertexDirection::South),
+        }
+    }
+
+    /// Returns the three cells that meet at this vertex
+    pub fn cells(&self) -> [HexCell; 3] {
+        let d = HexCell::directions();
+
+        match self.direction {
+            VertexDirection::North => [self.cell, self.cell + d[1], self.cell + d[2]],
+            VertexDirection::South => [self.cell, self.cell + d[4], self.cell + d[5]],
+        }
+    }
+
+    /// Returns the three edges that meet at this vertex
+    pub fn edges(&self) -> [HexEdge; 3] {
+        let d = HexCell::directions();
+
+        match self.direction {
+            VertexDirection::North => [
+                HexEdge::from_direction(self.cell, 1),
+                HexEdge::from_direction(self.cell, 2),
+                HexEdge::new(self.cell + d[2], EdgeDirection::East),
+            ],
+            VertexDirection::South => [
+                HexEdge::from_direction(self.cell, 4),
+                HexEdge::from_direction(self.cell, 5),
+                HexEdge::new(self.cell + d[4], EdgeDirection::East),
+            ],
+        }
+    }
+
+    /// Returns the three vertices connected to this vertex by an edge
+    pub fn adjacent_vertices(&self) -> [HexVertex; 3] {
+        match self.direction {
+            VertexDirection::North => [
+                Self::from_corner(self.cell, 1),
+                Self::from_corner(self.cell, 5),
+                Self::new(self.cell + HexCell::new(1, -2), VertexDirection::South),
+            ],
+            VertexDirection::South => [
+                Self::from_corner(self.cell, 2),
+                Self::from_corner(self.cell, 4),
+                Self::new(self.cell + HexCell::new(-1, 2), VertexDirection::North),
+            ],
+        }
+    }
+
+    /// Returns the world location of this vertex
+    pub fn world_location(&self) -> CartesianPoint {
+        let center = self.cell.world_location();
+
+        match self.direction {
+            VertexDirection::North => CartesianPoint::new(center.x, center.y + 1.0),
+            VertexDirection::South => CartesianPoint::new(center.x, center.y - 1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartesian_point::CartesianPoint;
+    use crate::hex_cell::HexCell;
+    use crate::hex_vertex::HexVertex;
+
+    fn close(a: CartesianPoint, b: CartesianPoint) -> bool {
+        (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4
+    }
+
+    #[test]
+    fn test_canonical() {
+        let h = HexCell::new(1, 3);
+
+        for (i, vertex) in h.vertices().into_iter().enumerate() {
+            assert_eq!(vertex, HexVertex::from_corner(h, i));
+            assert!(close(vertex.world_location(), h.vertex_locations()[i]));
+
+            // Every cell sharing the vertex addresses it the same way
+            for cell in vertex.cells() {
+                let corner = cell.vertices().iter().position(|v| *v == vertex).unwrap();
+
+                assert!(close(
+                    cell.vertex_locations()[corner],
+                    vertex.world_location()
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_adjacency() {
+        let h = HexCell::new(-2, 0);
+
+        for vertex in h.vertices() {
+            for edge in vertex.edges() {
+                assert!(edge.vertices().contains(&vertex));
+            }
+
+            for other in vertex.adjacent_vertices() {
+                let a = vertex.world_location();
+                let b = other.world_location();
+                let dist = ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt();
+
+                assert!((dist - 1.0).abs() < 1e-4);
+                assert!(vertex.edges().iter().any(|e| e.vertices().contains(&other)));
+            }
+        }
+    }
+}
@@ -0,0 +1,580 @@
+use crate::hex_cell::HexCell;
+use crate::hex_edge::HexEdge;
+use crate::honeycomb::Honeycomb;
+use crate::portal::Step;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// The position of a cluster, counted in clusters along the `q` and `r` axes
+type ClusterId = (i32, i32);
+
+/// Borders open across more than this many cells get an entrance at each end instead of a single
+/// one in the middle
+const MAX_ENTRANCE_WIDTH: usize = 6;
+
+/// A pathfinder for large honeycombs that searches a much smaller graph of clusters first,
+/// following HPA* (<https://webdocs.cs.ualberta.ca/~mmueller/ps/hpastar.pdf>).
+///
+/// The honeycomb is split into rhombic clusters of `cluster_size` cells along both the `q` and the
+/// `r` axis, each bordering six others. Every stretch of a border with passable cells on both sides
+/// gets an entrance, as does every portal between two clusters, and the cost of travelling between
+/// every two entrances of a cluster without leaving it is computed up front. Queries search the
+/// graph of entrances and then refine every step of the result into cells. The paths found are
+/// usually, but not always, as cheap as those of `Honeycomb::cheapest_path`.
+///
+/// The pathfinder does not borrow the honeycomb, so every change to the values of cells has to be
+/// passed to `HierarchicalPathfinder::update_cells()`, which only rebuilds the affected clusters.
+pub struct HierarchicalPathfinder<T, E = ()> {
+    cluster_size: i32,
+    cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+    /// The pairs of cells connected by the entrances of every border, keyed by the clusters on
+    /// either side of the border in ascending order
+    entrances: HashMap<(ClusterId, ClusterId), Vec<(HexCell, HexCell)>>,
+    clusters: HashMap<ClusterId, Cluster>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Cluster {
+    /// The edges of the graph of entrances leaving every entrance cell of this cluster, both to
+    /// other entrances of this cluster and across its borders
+    edges: HashMap<HexCell, Vec<(HexCell, usize)>>,
+}
+
+impl<T, E> HierarchicalPathfinder<T, E> {
+    /// Builds a pathfinder for the given honeycomb, with clusters spanning [`cluster_size`] cells
+    /// along each axis.
+    ///
+    /// [`cost_fn`] is kept by the pathfinder and only called while building clusters, here and in
+    /// `HierarchicalPathfinder::update_cells()`, since queries reuse the crossing costs computed
+    /// then. Changing what it returns therefore takes rebuilding the pathfinder.
+    pub fn new<V>(
+        honeycomb: &Honeycomb<T, E, V>,
+        cluster_size: usize,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+    ) -> Self {
+        assert!(cluster_size > 0, "clusters must contain at least one cell");
+
+        let mut pathfinder = Self {
+            cluster_size: i32::try_from(cluster_size).unwrap_or(i32::MAX),
+            cost_fn,
+            entrances: HashMap::new(),
+            clusters: HashMap::new(),
+        };
+
+        let clusters = honeycomb
+            .cells()
+            .map(|h| pathfinder.cluster_of(&h))
+            .collect::<HashSet<_>>();
+
+        for &cluster in &clusters {
+            for other in neighbor_clusters(cluster) {
+                if cluster < other && clusters.contains(&other) {
+                    pathfinder.build_border(honeycomb, cluster, other);
+                }
+            }
+        }
+
+        for cluster in clusters {
+            pathfinder.build_cluster(honeycomb, cluster);
+        }
+
+        pathfinder
+    }
+
+    pub fn cluster_size(&self) -> usize {
+        self.cluster_size as usize
+    }
+
+    /// Rebuilds the parts of the pathfinder affected by changes to the values of the given cells.
+    /// Changes to the value of an edge are passed as changes to both of its cells, and portals
+    /// added or removed as changes to both of the cells they connect.
+    ///
+    /// Only the clusters containing the given cells are rebuilt, along with the clusters across
+    /// the borders that any of them lie on and at the other end of their portals.
+    pub fn update_cells<V>(&mut self, honeycomb: &Honeycomb<T, E, V>, cells: &[HexCell]) {
+        let mut borders = HashSet::new();
+        let mut clusters = HashSet::new();
+
+        for cell in cells {
+            let cluster = self.cluster_of(cell);
+            clusters.insert(cluster);
+
+            // The clusters on the other end of portals cross into or out of this one
+            let portals = honeycomb.portals_from(cell).iter().map(|p| p.to);
+            for other in portals.chain(honeycomb.portals_into(cell).iter().map(|p| p.from)) {
+                clusters.insert(self.cluster_of(&other));
+            }
+
+            for neighbor in cell.neighbor_array() {
+                let other = self.cluster_of(&neighbor);
+                if other != cluster && honeycomb.index_of(&neighbor).is_some() {
+                    borders.insert((cluster.min(other), cluster.max(other)));
+                    clusters.insert(other);
+                }
+            }
+        }
+
+        for (a, b) in borders {
+            self.build_border(honeycomb, a, b);
+        }
+
+        for cluster in clusters {
+            self.build_cluster(honeycomb, cluster);
+        }
+    }
+
+    /// Calculates a path between `from` and `to`, or `None` if no such path exists.
+    ///
+    /// [`heuristic`] is first used between entrances, which are many cells apart, and then between
+    /// the cells of every step through a cluster, so an estimate that only holds for nearby cells
+    /// makes the path more expensive rather than just the search slower.
+    pub fn find_path<V>(
+        &self,
+        honeycomb: &Honeycomb<T, E, V>,
+        from: &HexCell,
+        to: &HexCell,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+    ) -> Option<Vec<HexCell>> {
+        let start = honeycomb.index_of(from)?;
+        honeycomb.index_of(to)?;
+
+        if from == to {
+            return Some(vec![*from]);
+        }
+
+        let start_cluster = self.cluster_of(from);
+        let goal_cluster = self.cluster_of(to);
+
+        // Connect both ends to the entrances of their clusters for the duration of the query
+        let from_costs = self.search_cluster(honeycomb, *from, false);
+        let to_costs = self.search_cluster(honeycomb, *to, true);
+
+        let mut reached = HashMap::from([(*from, (0, *from))]);
+        let mut open = BinaryHeap::from([Reverse((heuristic(from, to), start))]);
+        let mut edges = Vec::new();
+
+        // https://en.wikipedia.org/wiki/A*_search_algorithm
+        while let Some(Reverse((_, index))) = open.pop() {
+            let hex = honeycomb.cell_at(index);
+            if hex == *to {
+                break;
+            }
+
+            let cluster = self.clusters.get(&self.cluster_of(&hex));
+            let is_entrance = cluster.is_some_and(|c| c.edges.contains_key(&hex));
+
+            edges.clear();
+            if let Some(cluster_edges) = cluster.and_then(|c| c.edges.get(&hex)) {
+                edges.extend_from_slice(cluster_edges);
+            }
+
+            if hex == *from {
+                for (cell, (cost, _)) in &from_costs {
+                    let is_target = self
+                        .clusters
+                        .get(&start_cluster)
+                        .is_some_and(|c| c.edges.contains_key(cell));
+
+                    if is_target || (cell == to && start_cluster == goal_cluster) {
+                        edges.push((*cell, *cost));
+                    }
+                }
+
+                // Costs are collected from a map, so they are sorted to keep ties deterministic
+                edges.sort_by_key(|(cell, cost)| (*cost, cell.q, cell.r));
+            }
+
+            if is_entrance && self.cluster_of(&hex) == goal_cluster {
+                if let Some((cost, _)) = to_costs.get(&hex) {
+                    edges.push((*to, *cost));
+                }
+            }
+
+            let curr_cost = reached[&hex].0;
+            for &(next, cost) in &edges {
+                let new_cost = curr_cost + cost;
+                let is_cheaper = reached.get(&next).is_none_or(|(c, _)| new_cost < *c);
+
+                if is_cheaper {
+                    reached.insert(next, (new_cost, hex));
+
+                    let priority = new_cost + heuristic(&next, to);
+                    open.push(Reverse((priority, honeycomb.index_of(&next)?)));
+                }
+            }
+        }
+
+        // Follow the entrances back from the destination, then refine every step into cells
+        let mut waypoints = vec![*to];
+        while waypoints.last() != Some(from) {
+            let (_, came_from) = reached.get(waypoints.last()?)?;
+            waypoints.push(*came_from);
+        }
+        waypoints.reverse();
+
+        let mut path = vec![*from];
+        for step in waypoints.windows(2) {
+            let (a, b) = (step[0], step[1]);
+
+            // Steps between clusters cross a border or go through a portal
+            if self.cluster_of(&a) != self.cluster_of(&b) {
+                path.push(b);
+                continue;
+            }
+
+            let search = self.search_cluster(honeycomb, a, false);
+            let start = path.len();
+
+            let mut hex = b;
+            while hex != a {
+                path.push(hex);
+                hex = search.get(&hex)?.1;
+            }
+            path[start..].reverse();
+        }
+
+        Some(path)
+    }
+
+    fn cluster_of(&self, hex: &HexCell) -> ClusterId {
+        (
+            hex.q.div_euclid(self.cluster_size),
+            hex.r.div_euclid(self.cluster_size),
+        )
+    }
+
+    /// Returns the cells of the given cluster that lie inside the honeycomb
+    fn cells_of<V>(
+        &self,
+        honeycomb: &Honeycomb<T, E, V>,
+        cluster: ClusterId,
+    ) -> impl Iterator<Item = HexCell> + '_ {
+        let s = self.cluster_size;
+        let size = honeycomb.size();
+
+        (cluster.0 * s..(cluster.0 + 1) * s)
+            .flat_map(move |q| {
+                (cluster.1 * s..(cluster.1 + 1) * s).map(move |r| HexCell::new(q, r))
+            })
+            .filter(move |h| h.axial_dist_to(&HexCell::origin()) <= size)
+    }
+
+    /// Places the entrances of the border between clusters `a` and `b`, where `a < b`
+    fn build_border<V>(&mut self, honeycomb: &Honeycomb<T, E, V>, a: ClusterId, b: ClusterId) {
+        let mut crossings = Vec::new();
+        for hex in self.cells_of(honeycomb, a) {
+            if !honeycomb.data.contains_key(&hex) {
+                continue;
+            }
+
+            for neighbor in hex.neighbor_array() {
+                if self.cluster_of(&neighbor) == b
+                    && honeycomb.index_of(&neighbor).is_some()
+                    && honeycomb.data.contains_key(&neighbor)
+                {
+                    crossings.push((hex, neighbor));
+                }
+            }
+        }
+
+        // Crossings whose cells on both sides touch belong to the same stretch of open border
+        let touches = |x: &HexCell, y: &HexCell| x.axial_dist_to(y) <= 1;
+        let mut grouped = vec![false; crossings.len()];
+        let mut entrances = Vec::new();
+
+        for first in 0..crossings.len() {
+            if grouped[first] {
+                continue;
+            }
+
+            grouped[first] = true;
+            let mut stretch = vec![crossings[first]];
+            let mut i = 0;
+
+            while i < stretch.len() {
+                let (x, y) = stretch[i];
+                for (j, other) in crossings.iter().enumerate() {
+                    if !grouped[j] && touches(&x, &other.0) && touches(&y, &other.1) {
+                        grouped[j] = true;
+                        stretch.push(*other);
+                    }
+                }
+                i += 1;
+            }
+
+            if stretch.len() > MAX_ENTRANCE_WIDTH {
+                entrances.push(stretch[0]);
+                entrances.push(stretch[stretch.len() - 1]);
+            } else {
+                entrances.push(stretch[stretch.len() / 2]);
+            }
+        }
+
+        if entrances.is_empty() {
+            self.entrances.remove(&(a, b));
+        } else {
+            self.entrances.insert((a, b), entrances);
+        }
+    }
+
+    /// Computes the edges leaving every entrance of the given cluster
+    fn build_cluster<V>(&mut self, honeycomb: &Honeycomb<T, E, V>, cluster: ClusterId) {
+        // The crossings of every border of this cluster, starting on this side
+        let mut crossings = Vec::new();
+        for other in neighbor_clusters(cluster) {
+            let border = if cluster < other {
+                self.entrances.get(&(cluster, other)).map(|e| e.to_vec())
+            } else {
+                self.entrances
+                    .get(&(other, cluster))
+                    .map(|e| e.iter().map(|(x, y)| (*y, *x)).collect())
+            };
+
+            for (x, y) in border.unwrap_or_default() {
+                crossings.push((x, y, Step::Edge(HexEdge::between(&x, &y).unwrap())));
+            }
+        }
+
+        // Portals into other clusters cross just like borders do, but only one way, so the cells
+        // they lead into are entrances of their cluster without crossing back out of it
+        let mut landings = Vec::new();
+        for hex in self.cells_of(honeycomb, cluster) {
+            if !honeycomb.data.contains_key(&hex) {
+                continue;
+            }
+
+            for (other, _, step) in honeycomb.steps(hex, false) {
+                if let Step::Portal(_) = step {
+                    if self.cluster_of(&other) != cluster && honeycomb.data.contains_key(&other) {
+                        crossings.push((hex, other, step));
+                    }
+                }
+            }
+
+            for portal in honeycomb.portals_into(&hex) {
+                if self.cluster_of(&portal.from) != cluster
+                    && honeycomb.data.contains_key(&portal.from)
+                {
+                    landings.push(hex);
+                }
+            }
+        }
+
+        let entrances = crossings
+            .iter()
+            .map(|(hex, _, _)| *hex)
+            .chain(landings)
+            .collect::<Vec<_>>();
+
+        let mut edges: HashMap<HexCell, Vec<(HexCell, usize)>> = HashMap::new();
+        for &hex in &entrances {
+            if edges.contains_key(&hex) {
+                continue;
+            }
+
+            let search = self.search_cluster(honeycomb, hex, false);
+            let reachable = entrances
+                .iter()
+                .filter(|other| **other != hex)
+                .filter_map(|other| search.get(other).map(|(cost, _)| (*other, *cost)));
+
+            let mut cell_edges = Vec::new();
+            for (other, cost) in reachable {
+                if !cell_edges.iter().any(|(e, _)| *e == other) {
+                    cell_edges.push((other, cost));
+                }
+            }
+            edges.insert(hex, cell_edges);
+        }
+
+        for &(hex, neighbor, step) in &crossings {
+            let (curr_val, next_val) = (&honeycomb.data[&hex], &honeycomb.data[&neighbor]);
+            let cost = honeycomb.step_cost(curr_val, next_val, step, self.cost_fn);
+
+            if let Some(cell_edges) = edges.get_mut(&hex) {
+                cell_edges.push((neighbor, cost));
+            }
+        }
+
+        if edges.is_empty() {
+            self.clusters.remove(&cluster);
+        } else {
+            self.clusters.insert(cluster, Cluster { edges });
+        }
+    }
+
+    /// Runs Dijkstra's algorithm from [`from`] without leaving its cluster, returning the cost of
+    /// the cheapest path to every cell reached and the cell it was reached from. If [`reverse`],
+    /// the costs are those of the cheapest paths from every cell reached to [`from`] instead.
+    fn search_cluster<V>(
+        &self,
+        honeycomb: &Honeycomb<T, E, V>,
+        from: HexCell,
+        reverse: bool,
+    ) -> HashMap<HexCell, (usize, HexCell)> {
+        let cluster = self.cluster_of(&from);
+        let mut reached = HashMap::from([(from, (0, from))]);
+        let mut open = BinaryHeap::new();
+
+        if let Some(index) = honeycomb.index_of(&from) {
+            open.push(Reverse((0, index)));
+        }
+
+        while let Some(Reverse((cost, index))) = open.pop() {
+            let hex = honeycomb.cell_at(index);
+            if reached[&hex].0 < cost {
+                continue;
+            }
+
+            let Some(curr_val) = honeycomb.data.get(&hex) else {
+                continue;
+            };
+
+            for (neighbor, next, step) in honeycomb.steps(hex, reverse) {
+                if self.cluster_of(&neighbor) != cluster {
+                    continue;
+                }
+                let Some(next_val) = honeycomb.data.get(&neighbor) else {
+                    continue;
+                };
+
+                let step_cost = if reverse {
+                    honeycomb.step_cost(next_val, curr_val, step, self.cost_fn)
+                } else {
+                    honeycomb.step_cost(curr_val, next_val, step, self.cost_fn)
+                };
+
+                let new_cost = cost + step_cost;
+                if reached.get(&neighbor).is_none_or(|(c, _)| new_cost < *c) {
+                    reached.insert(neighbor, (new_cost, hex));
+                    open.push(Reverse((new_cost, next)));
+                }
+            }
+        }
+
+        reached
+    }
+}
+
+/// Returns the six clusters bordering the given one
+fn neighbor_clusters(cluster: ClusterId) -> [ClusterId; 6] {
+    HexCell::directions().map(|d| (cluster.0 + d.q, cluster.1 + d.r))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::hierarchical::HierarchicalPathfinder;
+    use crate::portal::Portal;
+    use crate::test_utils::{path_cost, portal_honeycomb, random_cell, random_honeycomb};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn cost_fn(a: &u8, b: &u8, _: Option<&()>) -> usize {
+        *a as usize + *b as usize
+    }
+
+    fn heuristic(a: &HexCell, b: &HexCell) -> usize {
+        a.axial_dist_to(b) * 2
+    }
+
+    #[test]
+    fn test_find_path() {
+        let honeycomb = random_honeycomb(&mut StdRng::seed_from_u64(44), 15, (3, 4), 1..4);
+        let pathfinder = HierarchicalPathfinder::new(&honeycomb, 5, cost_fn);
+        let mut rng = StdRng::seed_from_u64(44);
+
+        for _ in 0..300 {
+            let from = random_cell(&mut rng, &honeycomb);
+            let to = random_cell(&mut rng, &honeycomb);
+
+            let cheapest = honeycomb.cheapest_path(&from, &to, cost_fn, heuristic);
+            let path = pathfinder.find_path(&honeycomb, &from, &to, heuristic);
+
+            // Whenever there is a path it is found, and it is never cheaper than the cheapest one
+            assert_eq!(path.is_some(), cheapest.is_some());
+            if let (Some(path), Some(cheapest)) = (path, cheapest) {
+                assert_eq!((path[0], *path.last().unwrap()), (from, to));
+                assert!(
+                    path_cost(&honeycomb, &path, cost_fn)
+                        >= path_cost(&honeycomb, &cheapest, cost_fn)
+                );
+            }
+        }
+
+        // Cells outside the honeycomb have no path, even to themselves
+        let outside = HexCell::new(20, 0);
+        assert_eq!(
+            pathfinder.find_path(&honeycomb, &outside, &outside, heuristic),
+            None
+        );
+        assert_eq!(
+            honeycomb.cheapest_path(&outside, &outside, cost_fn, heuristic),
+            None
+        );
+    }
+
+    #[test]
+    fn test_update_cells() {
+        let mut honeycomb = random_honeycomb(&mut StdRng::seed_from_u64(45), 15, (3, 4), 1..4);
+        let mut pathfinder = HierarchicalPathfinder::new(&honeycomb, 4, cost_fn);
+        let mut rng = StdRng::seed_from_u64(45);
+
+        for _ in 0..20 {
+            let changed = (0..5)
+                .map(|_| random_cell(&mut rng, &honeycomb))
+                .collect::<Vec<_>>();
+
+            for h in &changed {
+                if honeycomb.data.remove(h).is_none() {
+                    honeycomb.data.insert(*h, rng.gen_range(1..4));
+                }
+            }
+            pathfinder.update_cells(&honeycomb, &changed);
+
+            // Updating only the affected clusters leaves the same state as rebuilding everything
+            let rebuilt = HierarchicalPathfinder::new(&honeycomb, 4, cost_fn);
+            assert_eq!(pathfinder.entrances, rebuilt.entrances);
+            assert_eq!(pathfinder.clusters, rebuilt.clusters);
+        }
+    }
+
+    #[test]
+    fn test_portals() {
+        let mut honeycomb = portal_honeycomb();
+        let from = HexCell::new(1, 1);
+        let to = HexCell::new(5, 1);
+        let through = [HexCell::new(2, 1), HexCell::new(4, 1)];
+
+        // The portal is the only way across, whether it joins two clusters or stays inside one
+        for cluster_size in 1..10 {
+            let pathfinder = HierarchicalPathfinder::new(&honeycomb, cluster_size, cost_fn);
+            let path = pathfinder
+                .find_path(&honeycomb, &from, &to, heuristic)
+                .unwrap();
+
+            assert_eq!((path[0], *path.last().unwrap()), (from, to));
+            for step in path.windows(2) {
+                assert!(step == through || step[0].axial_dist_to(&step[1]) == 1);
+            }
+            assert!(path.windows(2).any(|step| step == through));
+            assert_eq!(
+                pathfinder.find_path(&honeycomb, &to, &from, heuristic),
+                None
+            );
+        }
+
+        // A fixed cost makes the portal expensive enough to be worth walking around once the
+        // column opens up
+        let mut pathfinder = HierarchicalPathfinder::new(&honeycomb, 3, cost_fn);
+        let portal = Portal::new(HexCell::new(2, 1), HexCell::new(4, 1), Some(100));
+        honeycomb.add_portal(portal);
+        honeycomb.data.insert(HexCell::new(3, 1), 1);
+        pathfinder.update_cells(&honeycomb, &[portal.from, portal.to, HexCell::new(3, 1)]);
+
+        let path = pathfinder
+            .find_path(&honeycomb, &from, &to, heuristic)
+            .unwrap();
+        assert!(path_cost(&honeycomb, &path, cost_fn) < 100);
+    }
+}
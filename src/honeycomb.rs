@@ -1,15 +1,27 @@
-use crate::cartesian_point::CartesianPoint;
+use crate::cartesian_point::{CartesianPoint, Float};
 use crate::hex_cell::HexCell;
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap, VecDeque};
-
-pub struct Honeycomb<T> {
+use crate::hex_edge::HexEdge;
+use crate::hex_vertex::HexVertex;
+use crate::pathfinding::{PathfindingContext, SearchLimits, SearchStatus};
+use crate::portal::Portal;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub struct Honeycomb<T, E = (), V = ()> {
+    /// Every cell of this honeycomb. Searches and file formats find cells by their coordinates
+    /// through `Honeycomb::index_of()` and `Honeycomb::cells()` rather than by their position here,
+    /// so they are unaffected by changes to this.
     pub grid: Vec<HexCell>,
     pub data: HashMap<HexCell, T>,
+    pub edge_data: HashMap<HexEdge, E>,
+    pub vertex_data: HashMap<HexVertex, V>,
     size: usize,
+    /// The portals leading out of every cell, kept in sync with `portals_into`
+    pub(crate) portals_from: HashMap<HexCell, Vec<Portal>>,
+    /// The portals leading into every cell
+    pub(crate) portals_into: HashMap<HexCell, Vec<Portal>>,
 }
 
-impl<T> Honeycomb<T> {
+impl<T, E, V> Honeycomb<T, E, V> {
     /// Returns a new `Honeycomb` with the given size.
     pub fn new(size: usize) -> Self {
         let n = size as i32;
@@ -24,191 +36,885 @@ impl<T> Honeycomb<T> {
             }
         }
         let data = HashMap::new();
+        let edge_data = HashMap::new();
+        let vertex_data = HashMap::new();
+
+        Self {
+            grid,
+            data,
+            edge_data,
+            vertex_data,
+            size,
+            portals_from: HashMap::new(),
+            portals_into: HashMap::new(),
+        }
+    }
+
+    /// Returns the radius of this honeycomb, not counting the center cell
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the position of the given hex in `grid` as laid out by `Honeycomb::new`, or `None`
+    /// if the hex is outside of this honeycomb. This is computed from the coordinates of the hex
+    /// without searching the grid, so it keeps working even if `grid` is changed afterwards.
+    pub fn index_of(&self, hex: &HexCell) -> Option<usize> {
+        grid_index(self.size, hex)
+    }
+
+    /// Returns the number of cells in this honeycomb, which `index_of()` is always less than
+    pub(crate) fn grid_len(&self) -> usize {
+        3 * self.size * (self.size + 1) + 1
+    }
+
+    /// Returns the hex whose index is the given one, the inverse of `index_of()`
+    pub(crate) fn cell_at(&self, index: usize) -> HexCell {
+        grid_cell(self.size, index)
+    }
 
-        Self { grid, data, size }
+    /// Returns every cell of this honeycomb in the order of `Honeycomb::index_of()`, which is the
+    /// order `Honeycomb::new` lays out `grid` in
+    pub fn cells(&self) -> Cells {
+        Cells {
+            indices: 0..self.grid_len(),
+            size: self.size,
+        }
+    }
+
+    /// Returns the bottom left and top right corners of the smallest rectangle in world space that
+    /// contains every cell of this honeycomb
+    pub fn world_bounds(&self) -> (CartesianPoint, CartesianPoint) {
+        let n = self.size as f32;
+        let x = 3f32.sqrt() * (n + 0.5);
+        let y = 1.5 * n + 1.0;
+
+        (CartesianPoint::new(-x, -y), CartesianPoint::new(x, y))
+    }
+
+    /// Returns every edge that borders at least one cell of this honeycomb
+    pub fn edges(&self) -> Vec<HexEdge> {
+        let mut seen = HashSet::new();
+
+        self.cells()
+            .flat_map(|h| h.edges())
+            .filter(|e| seen.insert(*e))
+            .collect::<Vec<_>>()
+    }
+
+    /// Returns every vertex that touches at least one cell of this honeycomb
+    pub fn vertices(&self) -> Vec<HexVertex> {
+        let mut seen = HashSet::new();
+
+        self.cells()
+            .flat_map(|h| h.vertices())
+            .filter(|v| seen.insert(*v))
+            .collect::<Vec<_>>()
     }
 
     /// Returns the hex on which the given point is located, or `None` if the point is outside the
     /// bounds of this honeycomb
-    pub fn world_to_hex(&self, point: CartesianPoint) -> Option<HexCell> {
-        let frac_q = 3f32.sqrt() / 3.0 * point.x - 1.0 / 3.0 * point.y;
-        let frac_r = 2.0 / 3.0 * point.y;
-        let frac_s = -frac_q - frac_r;
-
-        let q = frac_q.round();
-        let r = frac_r.round();
-        let s = frac_s.round();
-
-        let dq = (q - frac_q).abs();
-        let dr = (r - frac_r).abs();
-        let ds = (s - frac_s).abs();
-
-        let cell = if dq > dr && dq > ds {
-            HexCell::new((-r - s) as i32, r as i32)
-        } else if dr > ds {
-            HexCell::new(q as i32, (-q - s) as i32)
-        } else {
-            HexCell::new(q as i32, r as i32)
-        };
+    pub fn world_to_hex<F: Float>(&self, point: CartesianPoint<F>) -> Option<HexCell> {
+        let cell = HexCell::from_world(point);
 
-        if self.grid.contains(&cell) {
+        if cell.axial_dist_to(&HexCell::origin()) <= self.size {
             Some(cell)
         } else {
             None
         }
     }
 
+    /// Returns every cell of this honeycomb whose hexagon intersects or touches the axis-aligned
+    /// rectangle in world space with the given bottom left and top right corners.
+    ///
+    /// Only the cells near the rectangle are visited, so this is cheap even on large honeycombs.
+    pub fn cells_in_rect<F: Float>(
+        &self,
+        min: CartesianPoint<F>,
+        max: CartesianPoint<F>,
+    ) -> Vec<HexCell> {
+        let n = F::from_f64(self.size as f64);
+        let sqrt3 = F::from_f64(3f64.sqrt());
+        let (half, one, one_half) = (F::from_f64(0.5), F::ONE, F::from_f64(1.5));
+        let mut cells = Vec::new();
+
+        // Rows of cells spanning `-1.5r - 1..=-1.5r + 1` vertically that overlap the rectangle
+        let r1 = ((-max.y - one) / one_half).ceil().max(-n).to_f64() as i32;
+        let r2 = ((one - min.y) / one_half).floor().min(n).to_f64() as i32;
+
+        for r in r1..=r2 {
+            let row = F::from_f64(r as f64);
+
+            // Cells in this row spanning `sqrt(3) * (q + r / 2 +- 1 / 2)` horizontally that overlap
+            // the rectangle
+            let q1 = ((min.x / sqrt3) - (row + one) * half)
+                .ceil()
+                .max(-n)
+                .max(-n - row);
+            let q2 = ((max.x / sqrt3) - (row - one) * half)
+                .floor()
+                .min(n)
+                .min(n - row);
+
+            for q in q1.to_f64() as i32..=q2.to_f64() as i32 {
+                let cell = HexCell::new(q, r);
+
+                // The bounding boxes overlap, so the only remaining separating axes are the normals
+                // of the slanted sides of the hexagon
+                let center = cell.world_point::<F>();
+                let separated =
+                    [(half, sqrt3 * half), (-half, sqrt3 * half)]
+                        .iter()
+                        .any(|&(x, y)| {
+                            let corners = [
+                                min.x * x + min.y * y,
+                                max.x * x + min.y * y,
+                                min.x * x + max.y * y,
+                                max.x * x + max.y * y,
+                            ];
+                            let lo = corners.iter().copied().fold(F::MAX, F::min);
+                            let hi = corners.iter().copied().fold(F::MIN, F::max);
+                            let c = center.x * x + center.y * y;
+
+                            hi < c - sqrt3 * half || lo > c + sqrt3 * half
+                        });
+
+                if !separated {
+                    cells.push(cell);
+                }
+            }
+        }
+
+        cells
+    }
+
     /// Returns the shortest path between `from` and `to`, or `None` if no such path can be found.
     ///
     /// Uses a breadth-first approach to finding the path.
     ///
-    /// [`filter`] takes in the current cell's value and the new cell's value, and returns whether
-    /// it is legal to move across this cell
+    /// [`filter`] takes in the current cell's value, the new cell's value and the value of the edge
+    /// between them, and returns whether it is legal to move across this edge into the new cell
+    ///
+    /// Every step of the path, including the one into `to`, passes [`filter`], and cells without a
+    /// value are never entered. If `from` and `to` are the same cell, the path holds just that cell.
+    /// Returns `None` if either lies outside this honeycomb.
     pub fn shortest_path(
         &self,
         from: &HexCell,
         to: &HexCell,
-        filter: fn(curr_val: &T, new_val: &T) -> bool,
+        filter: fn(curr_val: &T, new_val: &T, edge_val: Option<&E>) -> bool,
     ) -> Option<Vec<HexCell>> {
-        let mut work_list = VecDeque::<HexCell>::new();
-        work_list.push_front(*from);
+        self.shortest_path_with(&mut PathfindingContext::new(), from, to, filter)
+            .map(|path| path.to_vec())
+    }
+
+    /// Returns every cell that can be reached from `from`, starting with `from` itself, in the
+    /// order in which a breadth-first search reaches them. Returns nothing if `from` lies outside
+    /// this honeycomb.
+    ///
+    /// [`filter`] is the same as for `Honeycomb::shortest_path`.
+    pub fn flood_fill(
+        &self,
+        from: &HexCell,
+        filter: fn(curr_val: &T, new_val: &T, edge_val: Option<&E>) -> bool,
+    ) -> Vec<HexCell> {
+        let Some(start) = self.index_of(from) else {
+            return Vec::new();
+        };
 
-        let mut came_from = HashMap::<HexCell, HexCell>::new();
+        let mut visited = vec![false; self.grid_len()];
+        let mut queue = VecDeque::from([start]);
+        let mut cells = Vec::new();
+        visited[start] = true;
 
-        // https://en.wikipedia.org/wiki/Breadth-first_search
-        while let Some(hex) = work_list.pop_back() {
-            for neighbor in self.neighbors_of(hex) {
-                // We found our target! Time to reconstruct the path
-                if neighbor == *to {
-                    return Self::reconstruct_path(*to, hex, *from, came_from);
-                }
+        while let Some(curr) = queue.pop_front() {
+            let hex = self.cell_at(curr);
+            cells.push(hex);
 
-                let seen = came_from.contains_key(&neighbor);
+            let Some(curr_val) = self.data.get(&hex) else {
+                continue;
+            };
 
-                let valid = if let (Some(curr), Some(new)) = (self.data.get(&hex), self.data.get(&neighbor)) {
-                    filter(curr, new)
-                } else {
-                    false
-                };
+            for (neighbor, next, step) in self.steps(hex, false) {
+                let valid = !visited[next]
+                    && self.data.get(&neighbor).is_some_and(|new_val| {
+                        filter(curr_val, new_val, self.step_edge_value(step))
+                    });
 
-                if !seen && valid {
-                    came_from.insert(neighbor, hex);
-                    work_list.push_front(neighbor);
+                if valid {
+                    visited[next] = true;
+                    queue.push_back(next);
                 }
             }
         }
 
-        None
+        cells
     }
 
     /// Calculates the cheapest path between `from` and `to` using the given cost function and
     /// heuristic.
     ///
-    /// [`cost_fn`] takes in the current cell's value and the new cell's value, and returns a usize
-    /// cost for movement
+    /// [`cost_fn`] takes in the current cell's value, the new cell's value and the value of the
+    /// edge between them, and returns a usize cost for movement
     ///
     /// [`heuristic`] takes in the current cell and the destination and cell, and returns a usize
     /// estimate for the distance to the destination. The speed of this function is heavily affected
     /// by the given heuristic function; the more strict the function is, the less additional paths
     /// will be explored. However, this function is ONLY guaranteed to find the cheapest path if the
     /// heuristic function NEVER overestimates the cost of reaching the destination.
-    pub fn cheapest_path( 
+    ///
+    /// As with `Honeycomb::shortest_path`, `to` needs a value to be reached, a path from a cell to
+    /// itself holds just that cell, and cells outside this honeycomb have no path. `to` is only
+    /// settled once it leaves the work list, so a cheaper path into it found later still wins over
+    /// the first one seen.
+    pub fn cheapest_path(
+        &self,
+        from: &HexCell,
+        to: &HexCell,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+    ) -> Option<Vec<HexCell>> {
+        self.cheapest_path_with(&mut PathfindingContext::new(), from, to, cost_fn, heuristic)
+            .map(|path| path.to_vec())
+    }
+
+    /// Same as `Honeycomb::cheapest_path`, but stops early once any of the given [`limits`] is hit,
+    /// returning the path towards the reached cell that [`heuristic`] estimates to be closest to
+    /// `to` instead. The returned status tells complete paths apart from partial ones, and the path
+    /// is empty if `to` is unreachable.
+    pub fn cheapest_path_limited(
+        &self,
+        from: &HexCell,
+        to: &HexCell,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+        limits: &SearchLimits<'_>,
+    ) -> (SearchStatus, Vec<HexCell>) {
+        let mut context = PathfindingContext::new();
+        let status =
+            self.cheapest_path_limited_with(&mut context, from, to, cost_fn, heuristic, limits);
+
+        (status, context.path().to_vec())
+    }
+
+    /// Calculates the cheapest path between `from` and `to` by searching from both ends at once
+    /// until the two searches meet, which explores far fewer cells than `Honeycomb::cheapest_path`
+    /// on long queries. The path found always has the same cost as that of `cheapest_path`.
+    ///
+    /// [`cost_fn`] is the same as for `Honeycomb::cheapest_path`, and does not need to be
+    /// symmetric: the search running backwards from `to` still calls it in the direction of travel.
+    ///
+    /// [`heuristic`] is the same as for `Honeycomb::cheapest_path`, but is also used to estimate the
+    /// cost of reaching cells from `from`, so it must be consistent as well as never overestimating:
+    /// for a step from `a` to `b`, neither `heuristic(a, c)` nor `heuristic(c, b)` may exceed the
+    /// cost of the step plus `heuristic(b, c)` or `heuristic(c, a)` respectively.
+    /// The axial distance times the cheapest cost of a single step is always consistent, and a
+    /// heuristic that always returns 0 turns this into a bidirectional Dijkstra search.
+    pub fn bidirectional_path(
         &self,
         from: &HexCell,
         to: &HexCell,
-        cost_fn: fn(curr_val: &T, next_val: &T) -> usize,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
         heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
     ) -> Option<Vec<HexCell>> {
-        let mut work_list = BinaryHeap::<HexWeight>::new();
-        work_list.push(HexWeight::new(0, *from));
+        self.bidirectional_path_with(&mut PathfindingContext::new(), from, to, cost_fn, heuristic)
+            .map(|path| path.to_vec())
+    }
 
-        let mut came_from = HashMap::<HexCell, HexCell>::new();
-        let mut cost_so_far = HashMap::<HexCell, usize>::new();
-        cost_so_far.insert(*from, 0);
+    /// Returns the value stored on the edge between `a` and `b`, if there is one
+    pub fn edge_value(&self, a: &HexCell, b: &HexCell) -> Option<&E> {
+        HexEdge::between(a, b).and_then(|e| self.edge_data.get(&e))
+    }
 
-        // https://en.wikipedia.org/wiki/A*_search_algorithm
-        while let Some(weight) = work_list.pop() {
-            let curr = weight.hex;
+    /// Returns the valid neighbors of the given hex, followed by the cells its portals lead to
+    pub fn neighbors_of(&self, hex: HexCell) -> Vec<HexCell> {
+        self.iter_neighbors_of(hex).collect::<Vec<_>>()
+    }
 
-            for neighbor in self.neighbors_of(curr) {
-                if &neighbor == to {
-                    return Self::reconstruct_path(*to, curr, *from, came_from);
-                }
+    /// Same as `Honeycomb::neighbors_of()`, without allocating
+    pub fn iter_neighbors_of(&self, hex: HexCell) -> NeighborsOf<'_> {
+        NeighborsOf {
+            neighbors: hex.neighbor_array().into_iter(),
+            portals: self.portals_from(&hex).iter(),
+            size: self.size,
+        }
+    }
+
+    /// Same as `Honeycomb::iter_neighbors_of()`, leaving out the cells its portals lead to
+    #[cfg(feature = "petgraph")]
+    pub(crate) fn iter_adjacent_of(&self, hex: HexCell) -> NeighborsOf<'_> {
+        NeighborsOf {
+            neighbors: hex.neighbor_array().into_iter(),
+            portals: [].iter(),
+            size: self.size,
+        }
+    }
+}
+
+/// An iterator over the neighbors of a hex that lie inside a honeycomb, in the same order as
+/// `HexCell::directions()`, followed by the cells its portals lead to. Returned by
+/// `Honeycomb::iter_neighbors_of()`.
+#[derive(Debug, Clone)]
+pub struct NeighborsOf<'a> {
+    neighbors: std::array::IntoIter<HexCell, 6>,
+    portals: std::slice::Iter<'a, Portal>,
+    size: usize,
+}
+
+impl Iterator for NeighborsOf<'_> {
+    type Item = HexCell;
+
+    fn next(&mut self) -> Option<HexCell> {
+        let size = self.size;
+        self.neighbors
+            .find(|n| n.axial_dist_to(&HexCell::origin()) <= size)
+            .or_else(|| self.portals.next().map(|p| p.to))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let portals = self.portals.len();
+        (portals, Some(self.neighbors.len() + portals))
+    }
+}
+
+/// An iterator over every cell of a honeycomb in the order of their indices. Returned by
+/// `Honeycomb::cells()`.
+#[derive(Debug, Clone)]
+pub struct Cells {
+    indices: std::ops::Range<usize>,
+    size: usize,
+}
+
+impl Iterator for Cells {
+    type Item = HexCell;
+
+    fn next(&mut self) -> Option<HexCell> {
+        self.indices.next().map(|index| grid_cell(self.size, index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Cells {}
+
+/// Returns the position of the given hex in the grid of a honeycomb of the given size. The grid
+/// holds columns of increasing `q`, where column `q` holds `2n + 1 - |q|` cells of increasing `r`.
+pub(crate) fn grid_index(size: usize, hex: &HexCell) -> Option<usize> {
+    if hex.axial_dist_to(&HexCell::origin()) > size {
+        return None;
+    }
+
+    let n = size as i64;
+    let (q, r) = (hex.q as i64, hex.r as i64);
+
+    // The number of cells in the first `m` columns, for `m <= n + 1`
+    let cells_before = |m: i64| m * (n + 1) + m * (m - 1) / 2;
+
+    // Columns past the center are counted backwards from the end, since the grid is symmetric
+    let column = q + n;
+    let start = if column <= n {
+        cells_before(column)
+    } else {
+        3 * n * (n + 1) + 1 - cells_before(2 * n - column + 1)
+    };
+
+    Some((start + r - std::cmp::max(-n, -q - n)) as usize)
+}
+
+/// Returns the hex at the given position in the grid of a honeycomb of the given size, the inverse
+/// of `grid_index()`. The index must be less than the number of cells in the grid.
+pub(crate) fn grid_cell(size: usize, index: usize) -> HexCell {
+    let n = size as i64;
+    let total = 3 * n * (n + 1) + 1;
+    let index = index as i64;
+
+    // The grid is symmetric, so the second half is the first half negated and counted backwards
+    if index > total / 2 {
+        let mirrored = grid_cell(size, (total - 1 - index) as usize);
+        return HexCell::new(-mirrored.q, -mirrored.r);
+    }
 
-                if let (Some(old_val), Some(new_val)) = (self.data.get(&curr), self.data.get(&neighbor)) {
-                    let new_cost = cost_so_far.get(&curr).unwrap() + cost_fn(old_val, new_val);
+    // The number of cells in the first `m` columns, for `m <= n + 1`
+    let cells_before = |m: i64| m * (n + 1) + m * (m - 1) / 2;
 
-                    let is_cheaper = if let Some(cost) = cost_so_far.get(&neighbor) {
-                        new_cost < *cost
-                    } else {
-                        true
-                    };
+    // Solve `cells_before(m) <= index` for the largest `m`, correcting for rounding
+    let b = (2 * n + 1) as f64;
+    let mut column = ((-b + (b * b + 8.0 * index as f64).sqrt()) / 2.0) as i64;
+    while column > 0 && cells_before(column) > index {
+        column -= 1;
+    }
+    while cells_before(column + 1) <= index {
+        column += 1;
+    }
+
+    let q = column - n;
+    let r = std::cmp::max(-n, -q - n) + index - cells_before(column);
 
-                    if is_cheaper {
-                        cost_so_far.insert(neighbor, new_cost);
+    HexCell::new(q as i32, r as i32)
+}
 
-                        let priority = new_cost + heuristic(&neighbor, to);
-                        work_list.push(HexWeight::new(priority, neighbor));
+/// Returns the number of cells in a honeycomb of the given size, or `None` if that overflows
+pub(crate) fn cell_count(size: usize) -> Option<usize> {
+    size.checked_add(1)?
+        .checked_mul(size)?
+        .checked_mul(3)?
+        .checked_add(1)
+}
 
-                        came_from.insert(neighbor, curr);
-                    }
+/// Honeycombs are serialized as their size plus the value of every cell in the order of
+/// `Honeycomb::cells()`, so that the cell coordinates themselves never need to be written out.
+/// Values on cells outside the grid, or on edges and vertices that don't touch it, can't be written
+/// this way and fail to serialize, and reading them back fails the same way.
+#[cfg(feature = "serde")]
+mod serialization {
+    use crate::hex_edge::HexEdge;
+    use crate::hex_vertex::HexVertex;
+    use crate::honeycomb::{cell_count, Honeycomb};
+    use crate::portal::Portal;
+    use serde::{de, ser};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize)]
+    struct HoneycombRef<'a, T, E, V> {
+        size: usize,
+        cells: Vec<Option<&'a T>>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        edges: Vec<(HexEdge, &'a E)>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        vertices: Vec<(HexVertex, &'a V)>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        portals: Vec<&'a Portal>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(bound(deserialize = "T: Deserialize<'de>, E: Deserialize<'de>, V: Deserialize<'de>"))]
+    struct HoneycombData<T, E, V> {
+        size: usize,
+        cells: Vec<Option<T>>,
+        #[serde(default)]
+        edges: Vec<(HexEdge, E)>,
+        #[serde(default)]
+        vertices: Vec<(HexVertex, V)>,
+        #[serde(default)]
+        portals: Vec<Portal>,
+    }
+
+    impl<T: Serialize, E: Serialize, V: Serialize> Serialize for Honeycomb<T, E, V> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let edges = self
+                .edges()
+                .into_iter()
+                .filter_map(|e| self.edge_data.get(&e).map(|val| (e, val)))
+                .collect::<Vec<_>>();
+
+            let vertices = self
+                .vertices()
+                .into_iter()
+                .filter_map(|v| self.vertex_data.get(&v).map(|val| (v, val)))
+                .collect::<Vec<_>>();
+
+            if self.data.keys().any(|h| self.index_of(h).is_none()) {
+                return Err(ser::Error::custom("cell value outside of the honeycomb"));
+            }
+            if edges.len() != self.edge_data.len() {
+                return Err(ser::Error::custom("edge value outside of the honeycomb"));
+            }
+            if vertices.len() != self.vertex_data.len() {
+                return Err(ser::Error::custom("vertex value outside of the honeycomb"));
+            }
+
+            HoneycombRef {
+                size: self.size,
+                cells: self.cells().map(|h| self.data.get(&h)).collect(),
+                edges,
+                vertices,
+                portals: self.cells().flat_map(|h| self.portals_from(&h)).collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, T, E, V> Deserialize<'de> for Honeycomb<T, E, V>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+        V: Deserialize<'de>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let data = HoneycombData::<T, E, V>::deserialize(deserializer)?;
+
+            // Checked before building the grid, so that a bogus size can't allocate a huge one
+            if cell_count(data.size) != Some(data.cells.len()) {
+                return Err(de::Error::invalid_length(
+                    data.cells.len(),
+                    &"one value per cell of the honeycomb",
+                ));
+            }
+
+            let mut honeycomb = Honeycomb::new(data.size);
+            let inside = |cells: &[_]| cells.iter().any(|h| honeycomb.index_of(h).is_some());
+
+            if !data.edges.iter().all(|(e, _)| inside(&e.cells())) {
+                return Err(de::Error::custom("edge value outside of the honeycomb"));
+            }
+            if !data.vertices.iter().all(|(v, _)| inside(&v.cells())) {
+                return Err(de::Error::custom("vertex value outside of the honeycomb"));
+            }
+
+            for (h, val) in honeycomb.cells().zip(data.cells) {
+                if let Some(val) = val {
+                    honeycomb.data.insert(h, val);
+                }
+            }
+
+            honeycomb.edge_data.extend(data.edges);
+            honeycomb.vertex_data.extend(data.vertices);
+
+            for portal in data.portals {
+                if !honeycomb.add_portal(portal) {
+                    return Err(de::Error::custom("portal leads outside of the honeycomb"));
                 }
             }
+
+            Ok(honeycomb)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::hex_edge::HexEdge;
+    use crate::honeycomb::Honeycomb;
+
+    fn open_honeycomb<E>(size: usize) -> Honeycomb<bool, E> {
+        let mut honeycomb = Honeycomb::new(size);
+
+        for h in honeycomb.grid.iter() {
+            honeycomb.data.insert(*h, true);
+        }
+
+        honeycomb
+    }
+
+    #[test]
+    fn test_index_of() {
+        for size in (0..6).chain([50, 500]) {
+            let honeycomb = Honeycomb::<()>::new(size);
+
+            for (i, h) in honeycomb.grid.iter().enumerate() {
+                assert_eq!(honeycomb.index_of(h), Some(i));
+                assert_eq!(honeycomb.cell_at(i), *h);
+            }
+            assert_eq!(honeycomb.cells().collect::<Vec<_>>(), honeycomb.grid);
+
+            let outside = HexCell::new(size as i32 + 1, 0);
+            assert_eq!(honeycomb.index_of(&outside), None);
+        }
+    }
+
+    #[test]
+    fn test_iter_neighbors_of() {
+        let honeycomb = Honeycomb::<()>::new(2);
+
+        for h in honeycomb.grid.iter() {
+            let neighbors = honeycomb.iter_neighbors_of(*h).collect::<Vec<_>>();
+            assert_eq!(neighbors, honeycomb.neighbors_of(*h));
+            assert!(neighbors.iter().all(|n| honeycomb.index_of(n).is_some()));
         }
 
-        None
+        // Corners have three neighbors inside the honeycomb, the center has all six
+        assert_eq!(honeycomb.iter_neighbors_of(HexCell::new(2, 0)).count(), 3);
+        assert_eq!(honeycomb.iter_neighbors_of(HexCell::origin()).count(), 6);
     }
 
-    /// Returns the valid neighbors of the given hex
-    pub fn neighbors_of(&self, hex: HexCell) -> Vec<HexCell> {
-        HexCell::directions()
-            .into_iter()
-            .map(|d| d + hex)
-            .filter(|n| n.axial_dist_to(&HexCell::origin()) <= self.size)
-            .collect::<Vec<_>>()
+    #[test]
+    fn test_edge_filter() {
+        let mut honeycomb = open_honeycomb::<bool>(1);
+        let (a, b) = (HexCell::origin(), HexCell::new(1, 0));
+
+        let path = honeycomb.shortest_path(&a, &b, |_, new, wall| *new && wall.is_none());
+        assert_eq!(path, Some(vec![a, b]));
+
+        // Wall off the edge between the two cells, forcing the path around it
+        honeycomb.edge_data.insert(HexEdge::between(&a, &b).unwrap(), true);
+
+        let path = honeycomb
+            .shortest_path(&a, &b, |_, new, wall| *new && wall.is_none())
+            .unwrap();
+        assert_eq!(path.len(), 3);
+        assert_eq!(path.first(), Some(&a));
+        assert_eq!(path.last(), Some(&b));
     }
 
-    /// Reconstructs the path from the destination to the start given a map from cell to cell
-    fn reconstruct_path(dest: HexCell, penultimate: HexCell, start: HexCell, came_from: HashMap<HexCell, HexCell>) -> Option<Vec<HexCell>> {
-        let mut path = vec![dest, penultimate];
+    #[test]
+    fn test_edge_cost() {
+        let mut honeycomb = open_honeycomb::<usize>(2);
+        let (a, b) = (HexCell::new(-1, 0), HexCell::new(1, 0));
+
+        // A river between the origin and its eastern neighbor makes the straight path expensive
+        honeycomb
+            .edge_data
+            .insert(HexEdge::between(&HexCell::origin(), &b).unwrap(), 10);
+
+        let path = honeycomb
+            .cheapest_path(
+                &a,
+                &b,
+                |_, _, river| 1 + river.copied().unwrap_or(0),
+                |a, b| a.axial_dist_to(b),
+            )
+            .unwrap();
+
+        assert_eq!(path.len(), 4);
+        assert!(!path.windows(2).any(|w| w == [HexCell::origin(), b]));
+    }
 
-        // While we have not returned to the starting cell, append the previous cell to
-        // the path
-        while let Some(v) = came_from.get(path.last().unwrap()) {
-            path.push(*v);
+    #[test]
+    fn test_invalid_target() {
+        let mut honeycomb = open_honeycomb::<()>(1);
+        let (a, b) = (HexCell::origin(), HexCell::new(1, 0));
+        let heuristic = |a: &HexCell, b: &HexCell| a.axial_dist_to(b);
+
+        // The step into the target has to pass the filter like any other
+        honeycomb.data.insert(b, false);
+        assert_eq!(honeycomb.shortest_path(&a, &b, |_, new, _| *new), None);
+        assert!(honeycomb
+            .cheapest_path(&a, &b, |_, _, _| 1, heuristic)
+            .is_some());
+
+        // A target without a value can't be entered at all
+        honeycomb.data.remove(&b);
+        assert_eq!(honeycomb.shortest_path(&a, &b, |_, _, _| true), None);
+        assert_eq!(
+            honeycomb.cheapest_path(&a, &b, |_, _, _| 1, heuristic),
+            None
+        );
+    }
 
-            if v == &start {
-                // path goes from end -> start, we want it the other way around
-                path.reverse();
+    #[test]
+    fn test_target_settled_late() {
+        let mut honeycomb = open_honeycomb::<usize>(2);
+        let (a, b) = (HexCell::origin(), HexCell::new(1, 0));
+
+        // The target is seen across the river first, but going around it is cheaper
+        honeycomb
+            .edge_data
+            .insert(HexEdge::between(&a, &b).unwrap(), 10);
+
+        let path = honeycomb
+            .cheapest_path(
+                &a,
+                &b,
+                |_, _, river| 1 + river.copied().unwrap_or(0),
+                |_, _| 0,
+            )
+            .unwrap();
+        assert_eq!(path.len(), 3);
+    }
 
-                return Some(path);
-            }
+    #[test]
+    fn test_same_cell() {
+        let honeycomb = open_honeycomb::<()>(1);
+        let heuristic = |a: &HexCell, b: &HexCell| a.axial_dist_to(b);
+
+        for hex in [HexCell::origin(), HexCell::new(1, -1)] {
+            let expected = Some(vec![hex]);
+            assert_eq!(
+                honeycomb.shortest_path(&hex, &hex, |_, _, _| true),
+                expected
+            );
+            assert_eq!(
+                honeycomb.cheapest_path(&hex, &hex, |_, _, _| 1, heuristic),
+                expected
+            );
+            assert_eq!(
+                honeycomb.bidirectional_path(&hex, &hex, |_, _, _| 1, heuristic),
+                expected
+            );
         }
 
-        None
+        // Cells outside the honeycomb have no path, not even to themselves
+        let outside = HexCell::new(2, 0);
+        assert_eq!(
+            honeycomb.shortest_path(&outside, &outside, |_, _, _| true),
+            None
+        );
+        assert_eq!(
+            honeycomb.cheapest_path(&outside, &outside, |_, _, _| 1, heuristic),
+            None
+        );
+        assert_eq!(
+            honeycomb.bidirectional_path(&outside, &outside, |_, _, _| 1, heuristic),
+            None
+        );
     }
-}
 
-#[derive(Debug, PartialEq, Eq)]
-struct HexWeight {
-    pub cost: usize,
-    pub hex: HexCell,
-}
+    #[test]
+    fn test_maze() {
+        let honeycomb = Honeycomb::<bool>::from_ascii(
+            "
+                   . . . .
+                  # # # . .
+                 . . . # . .
+                . # . . # . .
+                 . # # # # .
+                  . # # . .
+                   . . . .
+            ",
+            |c| Some(c == '.'),
+        )
+        .unwrap();
+
+        let (from, to) = (HexCell::origin(), HexCell::new(0, -3));
+
+        let path = honeycomb.shortest_path(&from, &to, |_, new, _| *new).unwrap();
+        let cheapest = honeycomb
+            .cheapest_path(&from, &to, |_, new, _| if *new { 1 } else { 100 }, |a, b| a.axial_dist_to(b))
+            .unwrap();
+
+        assert_eq!(path.len(), 19);
+        assert_eq!(cheapest.len(), 19);
+        assert!(path.iter().all(|h| honeycomb.data.get(h) == Some(&true)));
+        assert!(cheapest.iter().all(|h| honeycomb.data.get(h) == Some(&true)));
+    }
+
+    #[test]
+    fn test_cells_in_rect() {
+        use crate::cartesian_point::CartesianPoint;
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
 
-impl HexWeight {
-    fn new(cost: usize, hex: HexCell) -> Self {
-        Self { cost, hex }
+        fn cross(o: CartesianPoint, a: CartesianPoint, b: CartesianPoint) -> f32 {
+            (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+        }
+
+        fn segments_cross(
+            a: CartesianPoint,
+            b: CartesianPoint,
+            c: CartesianPoint,
+            d: CartesianPoint,
+        ) -> bool {
+            cross(a, b, c) * cross(a, b, d) < 0.0 && cross(c, d, a) * cross(c, d, b) < 0.0
+        }
+
+        let honeycomb = Honeycomb::<()>::new(6);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..200 {
+            let (x1, x2) = (rng.gen_range(-14.0..14.0), rng.gen_range(-14.0..14.0));
+            let (y1, y2) = (rng.gen_range(-12.0..12.0), rng.gen_range(-12.0..12.0));
+            let min = CartesianPoint::new(f32::min(x1, x2), f32::min(y1, y2));
+            let max = CartesianPoint::new(f32::max(x1, x2), f32::max(y1, y2));
+
+            let rect = [
+                min,
+                CartesianPoint::new(max.x, min.y),
+                max,
+                CartesianPoint::new(min.x, max.y),
+            ];
+
+            // Brute force over the whole grid, checking for contained corners or crossing sides
+            let expected = honeycomb
+                .grid
+                .iter()
+                .filter(|h| {
+                    let hex = h.vertex_locations();
+                    let center = h.world_location();
+
+                    let corner_in_rect = hex
+                        .iter()
+                        .any(|p| p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y);
+                    let corner_in_hex = rect
+                        .iter()
+                        .any(|p| (0..6).all(|i| cross(hex[i], hex[(i + 1) % 6], *p) <= 0.0));
+                    let center_in_rect = center.x >= min.x
+                        && center.x <= max.x
+                        && center.y >= min.y
+                        && center.y <= max.y;
+                    let sides_cross = (0..6).any(|i| {
+                        (0..4).any(|j| {
+                            segments_cross(hex[i], hex[(i + 1) % 6], rect[j], rect[(j + 1) % 4])
+                        })
+                    });
+
+                    corner_in_rect || corner_in_hex || center_in_rect || sides_cross
+                })
+                .copied()
+                .collect::<Vec<_>>();
+
+            let mut found = honeycomb.cells_in_rect(min, max);
+            assert_eq!(found.len(), expected.len());
+
+            found.retain(|h| !expected.contains(h));
+            assert!(found.is_empty());
+        }
+
+        let inside = honeycomb.cells_in_rect(
+            CartesianPoint::new(-0.1, -0.1),
+            CartesianPoint::new(0.1, 0.2),
+        );
+        assert_eq!(inside, vec![HexCell::origin()]);
+
+        let outside = honeycomb.cells_in_rect(
+            CartesianPoint::new(50.0, 0.0),
+            CartesianPoint::new(60.0, 1.0),
+        );
+        assert!(outside.is_empty());
     }
-}
 
-impl PartialOrd<Self> for HexWeight {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut honeycomb = Honeycomb::<u8, bool>::new(2);
+
+        for (i, h) in honeycomb.grid.iter().enumerate().skip(1) {
+            honeycomb.data.insert(*h, i as u8);
+        }
+        honeycomb.edge_data.insert(HexEdge::from_direction(HexCell::origin(), 4), true);
+        let portal = crate::portal::Portal::new(HexCell::new(2, 0), HexCell::new(-2, 0), Some(3));
+        assert!(honeycomb.add_portal(portal));
+
+        let json = serde_json::to_string(&honeycomb).unwrap();
+        let loaded = serde_json::from_str::<Honeycomb<u8, bool>>(&json).unwrap();
+
+        assert_eq!(loaded.grid, honeycomb.grid);
+        assert_eq!(loaded.data, honeycomb.data);
+        assert_eq!(loaded.edge_data, honeycomb.edge_data);
+        assert!(loaded.vertex_data.is_empty());
+        assert_eq!(loaded.portals().collect::<Vec<_>>(), vec![&portal]);
+
+        let truncated = json.replacen("null,", "", 1);
+        assert!(serde_json::from_str::<Honeycomb<u8, bool>>(&truncated).is_err());
     }
-}
 
-impl Ord for HexWeight {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.cost.cmp(&self.cost)
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_outside() {
+        let mut honeycomb = Honeycomb::<u8, bool>::new(1);
+        let near = HexEdge::from_direction(HexCell::origin(), 0);
+        let far = HexEdge::from_direction(HexCell::new(5, 0), 0);
+
+        honeycomb.edge_data.insert(near, true);
+        let json = serde_json::to_string(&honeycomb).unwrap();
+
+        // Values that can't be written in grid order are rejected instead of dropped
+        honeycomb.data.insert(HexCell::new(5, 0), 1);
+        assert!(serde_json::to_string(&honeycomb).is_err());
+        honeycomb.data.clear();
+        honeycomb.edge_data.insert(far, true);
+        assert!(serde_json::to_string(&honeycomb).is_err());
+
+        let moved = json.replace(
+            &serde_json::to_string(&near).unwrap(),
+            &serde_json::to_string(&far).unwrap(),
+        );
+        assert_ne!(moved, json);
+        assert!(serde_json::from_str::<Honeycomb<u8, bool>>(&moved).is_err());
+
+        // A huge size is rejected before its grid is ever built
+        let huge = r#"{"size":4000000000,"cells":[null]}"#;
+        assert!(serde_json::from_str::<Honeycomb<u8, bool>>(huge).is_err());
     }
 }
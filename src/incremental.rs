@@ -0,0 +1,336 @@
+use crate::hex_cell::HexCell;
+use crate::honeycomb::{grid_index, Honeycomb};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A planner that keeps the cheapest path between a moving start and a fixed goal up to date as
+/// the values of cells change, following D* Lite (<http://idm-lab.org/bib/abstracts/papers/aaai02b.pdf>).
+///
+/// The search runs backwards from the goal and keeps the cost of reaching the goal from every cell
+/// it has explored. When cells change, only the costs that depend on them are repaired, so
+/// replanning after every step of an agent that discovers obstacles as it goes costs a fraction of
+/// a new `Honeycomb::cheapest_path` query.
+///
+/// The planner does not borrow the honeycomb, so every call has to pass the same honeycomb it was
+/// created for, and every change to the values of cells has to be passed to
+/// `IncrementalPlanner::update_cells()` before the next call to `IncrementalPlanner::path()`.
+pub struct IncrementalPlanner<T, E = ()> {
+    size: usize,
+    start: HexCell,
+    goal: HexCell,
+    /// Where the start was when the keys in the work list were last made comparable
+    last_start: HexCell,
+    /// How much the keys in the work list underestimate their current value, grown every time the
+    /// start moves
+    key_modifier: usize,
+    /// The cost of reaching the goal from every cell, as of the last time it was expanded
+    costs: Vec<usize>,
+    /// The cost of reaching the goal from every cell, looking one step ahead
+    lookahead: Vec<usize>,
+    open: BinaryHeap<Reverse<((usize, usize), usize)>>,
+    cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+    heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+}
+
+impl<T, E> IncrementalPlanner<T, E> {
+    /// Returns a planner for paths from `start` to `goal`, or `None` if either lies outside the
+    /// honeycomb. No searching happens until the first call to `IncrementalPlanner::path()`.
+    ///
+    /// [`cost_fn`] is kept by the planner and called again for the steps around every cell passed to
+    /// `IncrementalPlanner::update_cells()`, so it has to keep returning the same cost for a step
+    /// until one of its cells is reported as changed.
+    ///
+    /// [`heuristic`] estimates the cost of reaching cells from the start rather than the cost of
+    /// reaching the goal, and must be consistent as well as never overestimating. The axial
+    /// distance times the cheapest cost of a single step is always consistent.
+    pub fn new<V>(
+        honeycomb: &Honeycomb<T, E, V>,
+        start: HexCell,
+        goal: HexCell,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+    ) -> Option<Self> {
+        honeycomb.index_of(&start)?;
+        let goal_index = honeycomb.index_of(&goal)?;
+
+        let mut planner = Self {
+            size: honeycomb.size(),
+            start,
+            goal,
+            last_start: start,
+            key_modifier: 0,
+            costs: vec![usize::MAX; honeycomb.grid_len()],
+            lookahead: vec![usize::MAX; honeycomb.grid_len()],
+            open: BinaryHeap::new(),
+            cost_fn,
+            heuristic,
+        };
+
+        planner.lookahead[goal_index] = 0;
+        let key = planner.key(&goal, goal_index);
+        planner.open.push(Reverse((key, goal_index)));
+
+        Some(planner)
+    }
+
+    pub fn start(&self) -> HexCell {
+        self.start
+    }
+
+    pub fn goal(&self) -> HexCell {
+        self.goal
+    }
+
+    /// Moves the start to the given cell, usually the next one along the current path. Returns
+    /// `false` and leaves the start unchanged if the cell lies outside the honeycomb.
+    pub fn move_to(&mut self, hex: HexCell) -> bool {
+        if grid_index(self.size, &hex).is_none() {
+            return false;
+        }
+
+        self.key_modifier = self
+            .key_modifier
+            .saturating_add((self.heuristic)(&self.last_start, &hex));
+        self.last_start = hex;
+        self.start = hex;
+
+        true
+    }
+
+    /// Repairs the search after changes to the values of the given cells. Changes to the value of
+    /// an edge are passed as changes to both of its cells, and portals added or removed as changes
+    /// to the cell they lead out of.
+    pub fn update_cells<V>(&mut self, honeycomb: &Honeycomb<T, E, V>, cells: &[HexCell]) {
+        for cell in cells {
+            let Some(index) = honeycomb.index_of(cell) else {
+                continue;
+            };
+
+            // Every step into or out of the cell may have changed its cost, which changes the
+            // lookahead of the cell and of every cell stepping into it
+            self.update_cell(honeycomb, index);
+            for (_, prev, _) in honeycomb.steps(*cell, true) {
+                self.update_cell(honeycomb, prev);
+            }
+        }
+    }
+
+    /// Returns the cheapest path from the start to the goal, or `None` if no such path exists.
+    /// Only the part of the search invalidated by changes since the last call is redone.
+    pub fn path<V>(&mut self, honeycomb: &Honeycomb<T, E, V>) -> Option<Vec<HexCell>> {
+        self.compute_costs(honeycomb);
+
+        let mut index = honeycomb.index_of(&self.start)?;
+        if self.lookahead[index] == usize::MAX {
+            return None;
+        }
+
+        let mut path = vec![self.start];
+        while path.len() <= honeycomb.grid_len() {
+            let hex = honeycomb.cell_at(index);
+            if hex == self.goal {
+                return Some(path);
+            }
+
+            // Step to the neighbor the cost of reaching the goal was computed through
+            let (_, next) = self
+                .successors(honeycomb, index)
+                .min_by_key(|(step, next)| step.saturating_add(self.costs[*next]))?;
+
+            index = next;
+            path.push(honeycomb.cell_at(index));
+        }
+
+        None
+    }
+
+    fn key(&self, hex: &HexCell, index: usize) -> (usize, usize) {
+        let cost = self.costs[index].min(self.lookahead[index]);
+        let estimate = cost
+            .saturating_add((self.heuristic)(&self.start, hex))
+            .saturating_add(self.key_modifier);
+
+        (estimate, cost)
+    }
+
+    /// Returns the cost of every step out of the given cell, along with the cell it steps into
+    fn successors<'a, V>(
+        &'a self,
+        honeycomb: &'a Honeycomb<T, E, V>,
+        index: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let hex = honeycomb.cell_at(index);
+        let curr_val = honeycomb.data.get(&hex);
+
+        honeycomb
+            .steps(hex, false)
+            .filter_map(move |(neighbor, next, step)| {
+                let next_val = honeycomb.data.get(&neighbor)?;
+                let cost = honeycomb.step_cost(curr_val?, next_val, step, self.cost_fn);
+
+                Some((cost, next))
+            })
+    }
+
+    /// Recomputes the lookahead cost of the given cell, and queues it if it no longer matches its
+    /// cost
+    fn update_cell<V>(&mut self, honeycomb: &Honeycomb<T, E, V>, index: usize) {
+        let hex = honeycomb.cell_at(index);
+
+        if hex != self.goal {
+            self.lookahead[index] = self
+                .successors(honeycomb, index)
+                .map(|(step, next)| step.saturating_add(self.costs[next]))
+                .min()
+                .unwrap_or(usize::MAX);
+        }
+
+        if self.costs[index] != self.lookahead[index] {
+            let key = self.key(&hex, index);
+            self.open.push(Reverse((key, index)));
+        }
+    }
+
+    /// Expands cells until the cost of reaching the goal from the start is known
+    fn compute_costs<V>(&mut self, honeycomb: &Honeycomb<T, E, V>) {
+        let Some(start) = honeycomb.index_of(&self.start) else {
+            return;
+        };
+
+        while let Some(&Reverse((old_key, index))) = self.open.peek() {
+            let start_key = self.key(&self.start, start);
+            if old_key >= start_key && self.costs[start] == self.lookahead[start] {
+                break;
+            }
+
+            self.open.pop();
+
+            // Cells can be queued more than once, and only their latest entry is current
+            if self.costs[index] == self.lookahead[index] {
+                continue;
+            }
+
+            let hex = honeycomb.cell_at(index);
+            let new_key = self.key(&hex, index);
+            if old_key < new_key {
+                self.open.push(Reverse((new_key, index)));
+                continue;
+            } else if old_key > new_key {
+                continue;
+            }
+
+            if self.costs[index] > self.lookahead[index] {
+                self.costs[index] = self.lookahead[index];
+            } else {
+                self.costs[index] = usize::MAX;
+                self.update_cell(honeycomb, index);
+            }
+
+            // Only the cells stepping into this one look ahead through it
+            for (_, prev, _) in honeycomb.steps(hex, true) {
+                self.update_cell(honeycomb, prev);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::incremental::IncrementalPlanner;
+    use crate::test_utils::{path_cost, portal_honeycomb, random_cell, random_honeycomb};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn cost_fn(a: &u8, b: &u8, _: Option<&()>) -> usize {
+        *a as usize + 2 * *b as usize
+    }
+
+    fn heuristic(a: &HexCell, b: &HexCell) -> usize {
+        a.axial_dist_to(b) * 3
+    }
+
+    #[test]
+    fn test_replanning() {
+        let mut rng = StdRng::seed_from_u64(45);
+
+        for _ in 0..10 {
+            let mut honeycomb = random_honeycomb(&mut rng, 10, (4, 5), 1..4);
+
+            let mut start = HexCell::new(-10, 5);
+            let goal = HexCell::new(10, -5);
+            honeycomb.data.insert(start, 1);
+            honeycomb.data.insert(goal, 1);
+
+            let mut planner =
+                IncrementalPlanner::new(&honeycomb, start, goal, cost_fn, heuristic).unwrap();
+
+            while start != goal {
+                // Cells around the agent change as it walks, but never the cell it stands on
+                let changed = (0..3)
+                    .map(|_| random_cell(&mut rng, &honeycomb))
+                    .filter(|h| *h != start)
+                    .collect::<Vec<_>>();
+                for h in &changed {
+                    if honeycomb.data.remove(h).is_none() {
+                        honeycomb.data.insert(*h, rng.gen_range(1..4));
+                    }
+                }
+                planner.update_cells(&honeycomb, &changed);
+
+                // The repaired path is as cheap as one planned from scratch
+                let path = planner.path(&honeycomb);
+                let cheapest = honeycomb.cheapest_path(&start, &goal, cost_fn, heuristic);
+                assert_eq!(path.is_some(), cheapest.is_some());
+
+                let (Some(path), Some(cheapest)) = (path, cheapest) else {
+                    break;
+                };
+                assert_eq!((path[0], *path.last().unwrap()), (start, goal));
+                assert_eq!(
+                    path_cost(&honeycomb, &path, cost_fn),
+                    path_cost(&honeycomb, &cheapest, cost_fn)
+                );
+
+                start = path[1];
+                assert!(planner.move_to(start));
+                assert_eq!(planner.start(), start);
+            }
+        }
+    }
+
+    #[test]
+    fn test_outside() {
+        let honeycomb = Honeycomb::<u8>::new(2);
+        let inside = HexCell::origin();
+        let outside = HexCell::new(3, 0);
+
+        assert!(IncrementalPlanner::new(&honeycomb, inside, outside, cost_fn, heuristic).is_none());
+
+        let mut planner =
+            IncrementalPlanner::new(&honeycomb, inside, inside, cost_fn, heuristic).unwrap();
+        assert!(!planner.move_to(outside));
+        assert_eq!(planner.start(), inside);
+    }
+
+    #[test]
+    fn test_portals() {
+        let mut honeycomb = portal_honeycomb();
+        let from = HexCell::new(1, 1);
+        let to = HexCell::new(5, 1);
+
+        let mut planner =
+            IncrementalPlanner::new(&honeycomb, from, to, cost_fn, heuristic).unwrap();
+        let through = vec![from, HexCell::new(2, 1), HexCell::new(4, 1), to];
+        assert_eq!(planner.path(&honeycomb), Some(through));
+
+        let mut back = IncrementalPlanner::new(&honeycomb, to, from, cost_fn, heuristic).unwrap();
+        assert_eq!(back.path(&honeycomb), None);
+
+        // Removing the portal is repaired like any other change to the cell it leads out of
+        let portal = honeycomb.remove_portal(&HexCell::new(2, 1), &HexCell::new(4, 1));
+        planner.update_cells(&honeycomb, &[portal.unwrap().from]);
+        assert_eq!(planner.path(&honeycomb), None);
+    }
+}
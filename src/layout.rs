@@ -0,0 +1,105 @@
+use crate::cartesian_point::{CartesianPoint, Float};
+use crate::hex_cell::HexCell;
+use crate::honeycomb::Honeycomb;
+
+/// Maps world space onto an output surface such as an image or a vertex buffer.
+///
+/// World space has `y` pointing up, while the surface has `y` pointing down as is usual for images.
+/// Coordinates are `f32` by default, and `f64` for surfaces showing cells far from the origin.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Layout<F = f32> {
+    /// The number of surface units per world unit, which is the distance from the center of a hex
+    /// to any of its vertices
+    pub scale: F,
+    /// The location of the world origin on the surface
+    pub origin: CartesianPoint<F>,
+}
+
+impl<F: Float> Layout<F> {
+    pub fn new(scale: F, origin: CartesianPoint<F>) -> Self {
+        Self { scale, origin }
+    }
+
+    /// Returns the layout with the largest scale that fits every cell of the honeycomb into a
+    /// `width` by `height` surface, leaving `margin` units free around the edges
+    pub fn fit<T, E, V>(honeycomb: &Honeycomb<T, E, V>, width: F, height: F, margin: F) -> Self {
+        let (min, max) = honeycomb.world_bounds();
+        let (min, max) = (
+            CartesianPoint::new(F::from_f64(min.x as f64), F::from_f64(min.y as f64)),
+            CartesianPoint::new(F::from_f64(max.x as f64), F::from_f64(max.y as f64)),
+        );
+        let two = F::from_f64(2.0);
+
+        let scale_x = (width - two * margin) / (max.x - min.x);
+        let scale_y = (height - two * margin) / (max.y - min.y);
+        let scale = scale_x.min(scale_y);
+
+        let center = (min + max) / two;
+        let origin = CartesianPoint::new(
+            width / two - center.x * scale,
+            height / two + center.y * scale,
+        );
+
+        Self::new(scale, origin)
+    }
+
+    /// Returns the surface location of the given world location
+    pub fn world_to_surface(&self, point: CartesianPoint<F>) -> CartesianPoint<F> {
+        CartesianPoint::new(
+            self.origin.x + point.x * self.scale,
+            self.origin.y - point.y * self.scale,
+        )
+    }
+
+    /// Returns the world location of the given surface location
+    pub fn surface_to_world(&self, point: CartesianPoint<F>) -> CartesianPoint<F> {
+        CartesianPoint::new(
+            (point.x - self.origin.x) / self.scale,
+            (self.origin.y - point.y) / self.scale,
+        )
+    }
+
+    /// Returns the surface location of the center of the given hex
+    pub fn hex_center(&self, hex: &HexCell) -> CartesianPoint<F> {
+        self.world_to_surface(hex.world_point())
+    }
+
+    /// Returns the surface locations of the vertices of the given hex, in the same order as
+    /// `HexCell::vertex_locations()`
+    pub fn hex_corners(&self, hex: &HexCell) -> Vec<CartesianPoint<F>> {
+        hex.vertex_points()
+            .into_iter()
+            .map(|p| self.world_to_surface(p))
+            .collect::<Vec<_>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartesian_point::{CartesianPoint, Float};
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::layout::Layout;
+
+    #[test]
+    fn test_fit() {
+        let honeycomb = Honeycomb::<()>::new(4);
+        let layout = Layout::fit(&honeycomb, 800.0, 600.0, 10.0);
+
+        assert_eq!(
+            layout.hex_center(&HexCell::origin()),
+            CartesianPoint::new(400.0, 300.0)
+        );
+
+        for h in honeycomb.grid.iter() {
+            for p in layout.hex_corners(h) {
+                assert!(p.x >= 9.99 && p.x <= 790.01);
+                assert!(p.y >= 9.99 && p.y <= 590.01);
+            }
+        }
+
+        let p = CartesianPoint::new(3.5, -2.25);
+        let round_trip = layout.surface_to_world(layout.world_to_surface(p));
+        assert!((round_trip.x - p.x).abs() < 1e-4 && (round_trip.y - p.y).abs() < 1e-4);
+    }
+}
@@ -1,3 +1,27 @@
+pub mod coord;
 pub mod hex_cell;
+pub mod hex_edge;
+pub mod hex_vertex;
 pub mod honeycomb;
+pub mod pathfinding;
+pub mod hierarchical;
+pub mod incremental;
+pub mod flow_field;
+pub mod cooperative;
+pub mod smoothing;
+pub mod portal;
 pub mod cartesian_point;
+pub mod binary;
+pub mod ascii;
+pub mod layout;
+pub mod svg;
+pub mod mesh;
+pub mod viewport;
+#[cfg(feature = "raster")]
+pub mod raster;
+#[cfg(feature = "petgraph")]
+pub mod graph;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(test)]
+mod test_utils;
@@ -0,0 +1,185 @@
+use crate::hex_cell::HexCell;
+use crate::hex_vertex::HexVertex;
+use crate::honeycomb::Honeycomb;
+use crate::layout::Layout;
+use std::collections::HashMap;
+
+/// How the indices of a `Mesh` are grouped
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Primitive {
+    /// Every three indices form a triangle
+    Triangles,
+    /// Every two indices form a line segment
+    Lines,
+}
+
+/// Vertex and index buffers ready to be uploaded to a rendering engine
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mesh<A = ()> {
+    /// Surface location of every vertex
+    pub positions: Vec<[f32; 2]>,
+    /// Attributes of every vertex, in the same order as `positions`
+    pub attributes: Vec<A>,
+    pub indices: Vec<u32>,
+    pub primitive: Primitive,
+}
+
+impl<A> Mesh<A> {
+    /// Returns the number of vertices in this mesh
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Builds a triangle mesh covering every cell of this honeycomb, positioned by [`layout`].
+    ///
+    /// [`per_cell_attrs`] takes in a cell, its value if it has one, and the index of one of its
+    /// corners in the order of `HexCell::vertex_locations()`, and returns the attributes of that
+    /// corner, such as its color, texture coordinates or the id of the cell. Cells that meet at a
+    /// vertex share a single mesh vertex whenever they give it the same attributes, so a mesh
+    /// built with `|_, _, _| ()` has exactly one vertex per vertex of the grid.
+    ///
+    /// Every cell is made of four triangles with the same winding order.
+    pub fn build_mesh<A: Clone + PartialEq>(
+        &self,
+        layout: &Layout,
+        per_cell_attrs: fn(hex: &HexCell, val: Option<&T>, corner: usize) -> A,
+    ) -> Mesh<A> {
+        let mut mesh = Mesh {
+            positions: Vec::new(),
+            attributes: Vec::new(),
+            indices: Vec::with_capacity(self.grid.len() * 12),
+            primitive: Primitive::Triangles,
+        };
+        let mut shared = HashMap::<HexVertex, Vec<u32>>::new();
+
+        for h in self.grid.iter() {
+            let val = self.data.get(h);
+
+            let corners = h.vertices().into_iter().enumerate().map(|(i, vertex)| {
+                let attrs = per_cell_attrs(h, val, i);
+                let candidates = shared.entry(vertex).or_default();
+
+                if let Some(index) = candidates
+                    .iter()
+                    .find(|index| mesh.attributes[**index as usize] == attrs)
+                {
+                    return *index;
+                }
+
+                let p = layout.world_to_surface(vertex.world_location());
+                let index = mesh.positions.len() as u32;
+
+                mesh.positions.push([p.x, p.y]);
+                mesh.attributes.push(attrs);
+                candidates.push(index);
+
+                index
+            });
+            let corners = corners.collect::<Vec<_>>();
+
+            for i in 1..5 {
+                mesh.indices
+                    .extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+            }
+        }
+
+        mesh
+    }
+
+    /// Builds a line mesh tracing every edge of this honeycomb once, positioned by [`layout`]
+    pub fn build_outline_mesh(&self, layout: &Layout) -> Mesh {
+        let mut mesh = Mesh {
+            positions: Vec::new(),
+            attributes: Vec::new(),
+            indices: Vec::new(),
+            primitive: Primitive::Lines,
+        };
+        let mut shared = HashMap::<HexVertex, u32>::new();
+
+        for edge in self.edges() {
+            for vertex in edge.vertices() {
+                let index = *shared.entry(vertex).or_insert_with(|| {
+                    let p = layout.world_to_surface(vertex.world_location());
+
+                    mesh.positions.push([p.x, p.y]);
+                    mesh.attributes.push(());
+
+                    mesh.positions.len() as u32 - 1
+                });
+
+                mesh.indices.push(index);
+            }
+        }
+
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartesian_point::CartesianPoint;
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::layout::Layout;
+    use crate::mesh::Primitive;
+
+    #[test]
+    fn test_shared_vertices() {
+        let honeycomb = Honeycomb::<()>::new(2);
+        let layout = Layout::new(1.0, CartesianPoint::new(0.0, 0.0));
+
+        let mesh = honeycomb.build_mesh(&layout, |_, _, _| ());
+        assert_eq!(mesh.primitive, Primitive::Triangles);
+        assert_eq!(mesh.vertex_count(), honeycomb.vertices().len());
+        assert_eq!(mesh.indices.len(), honeycomb.grid.len() * 12);
+
+        let ids = honeycomb.build_mesh(&layout, |h, _, _| *h);
+        assert_eq!(ids.vertex_count(), honeycomb.grid.len() * 6);
+        assert_eq!(ids.indices.len(), honeycomb.grid.len() * 12);
+
+        // Every triangle of a cell is made from its own vertices
+        for (triangle, h) in ids.indices.chunks(12).zip(honeycomb.grid.iter()) {
+            assert!(triangle.iter().all(|i| ids.attributes[*i as usize] == *h));
+        }
+    }
+
+    #[test]
+    fn test_partially_shared_vertices() {
+        let mut honeycomb = Honeycomb::<bool>::new(1);
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, h == HexCell::origin());
+        }
+
+        let layout = Layout::new(10.0, CartesianPoint::new(50.0, 50.0));
+        let mesh = honeycomb.build_mesh(&layout, |_, val, _| *val.unwrap());
+
+        // The six corners of the center cell are split from the ring around it
+        assert_eq!(mesh.vertex_count(), honeycomb.vertices().len() + 6);
+
+        let corners = layout.hex_corners(&HexCell::origin());
+        let center = honeycomb
+            .grid
+            .iter()
+            .position(|h| *h == HexCell::origin())
+            .unwrap();
+        for i in &mesh.indices[center * 12..center * 12 + 12] {
+            let [x, y] = mesh.positions[*i as usize];
+            assert!(corners
+                .iter()
+                .any(|c| (c.x - x).abs() < 1e-3 && (c.y - y).abs() < 1e-3));
+        }
+    }
+
+    #[test]
+    fn test_outline() {
+        let honeycomb = Honeycomb::<()>::new(3);
+        let layout = Layout::new(1.0, CartesianPoint::new(0.0, 0.0));
+
+        let mesh = honeycomb.build_outline_mesh(&layout);
+        assert_eq!(mesh.primitive, Primitive::Lines);
+        assert_eq!(mesh.indices.len(), honeycomb.edges().len() * 2);
+        assert_eq!(mesh.vertex_count(), honeycomb.vertices().len());
+    }
+}
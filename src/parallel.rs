@@ -0,0 +1,64 @@
+use crate::hex_cell::HexCell;
+use crate::honeycomb::Honeycomb;
+use crate::pathfinding::PathfindingContext;
+use rayon::prelude::*;
+
+impl<T: Sync, E: Sync, V: Sync> Honeycomb<T, E, V> {
+    /// Calculates the cheapest path between every `(from, to)` pair of [`queries`] in parallel,
+    /// returning the results in the same order as the queries.
+    ///
+    /// Each result is the same as that of `Honeycomb::cheapest_path` with the same [`cost_fn`] and
+    /// [`heuristic`]. Every worker thread reuses a single `PathfindingContext` across the queries it
+    /// runs, so large batches don't allocate a new set of buffers per query.
+    pub fn cheapest_paths_batch(
+        &self,
+        queries: &[(HexCell, HexCell)],
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+    ) -> Vec<Option<Vec<HexCell>>> {
+        queries
+            .par_iter()
+            .map_init(PathfindingContext::new, |context, (from, to)| {
+                self.cheapest_path_with(context, from, to, cost_fn, heuristic)
+                    .map(|path| path.to_vec())
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::test_utils::{random_cell, random_honeycomb};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_batch_matches_sequential() {
+        let mut rng = StdRng::seed_from_u64(40);
+        // Leave some cells without a value so that a few queries have no path
+        let honeycomb = random_honeycomb(&mut rng, 12, (9, 10), 1..5);
+
+        let queries = (0..200)
+            .map(|_| {
+                let from = random_cell(&mut rng, &honeycomb);
+                let to = random_cell(&mut rng, &honeycomb);
+                (from, to)
+            })
+            .collect::<Vec<_>>();
+
+        let cost_fn = |_: &u8, b: &u8, _: Option<&()>| *b as usize;
+        let heuristic = |a: &HexCell, b: &HexCell| a.axial_dist_to(b);
+
+        let batch = honeycomb.cheapest_paths_batch(&queries, cost_fn, heuristic);
+        assert_eq!(batch.len(), queries.len());
+
+        for ((from, to), path) in queries.iter().zip(batch) {
+            assert_eq!(path, honeycomb.cheapest_path(from, to, cost_fn, heuristic));
+
+            if let Some(path) = path {
+                assert_eq!((path[0], *path.last().unwrap()), (*from, *to));
+            }
+        }
+    }
+}
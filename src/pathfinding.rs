@@ -0,0 +1,815 @@
+use crate::hex_cell::HexCell;
+use crate::honeycomb::{grid_cell, Honeycomb};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+
+/// Buffers for pathfinding queries that are owned by the caller and reused from one query to the
+/// next. Once they have grown to fit the largest honeycomb searched, queries made with the same
+/// context no longer allocate.
+///
+/// Per-cell state is kept in dense arrays indexed by `Honeycomb::index_of()`. Every query starts a
+/// new generation, and an entry only counts as set if it was stamped with the current generation,
+/// so the arrays never need to be cleared between queries.
+#[derive(Debug, Default)]
+pub struct PathfindingContext {
+    generation: u32,
+    /// The generation in which every cell was last reached
+    stamps: Vec<u32>,
+    /// The cost of the cheapest known path to every reached cell
+    costs: Vec<usize>,
+    /// The index of the cell every reached cell was reached from
+    came_from: Vec<usize>,
+    heap: BinaryHeap<HexWeight>,
+    queue: VecDeque<usize>,
+    path: Vec<HexCell>,
+    /// The generation in which every cell was last reached by a search running backwards from the
+    /// destination
+    reverse_stamps: Vec<u32>,
+    /// The cost of the cheapest known path from every cell reached backwards to the destination
+    reverse_costs: Vec<usize>,
+    /// The index of the cell every cell reached backwards leads to on its way to the destination
+    goes_to: Vec<usize>,
+    /// Work lists of bidirectional searches, whose priorities can be negative
+    forward_open: BinaryHeap<Reverse<(isize, usize)>>,
+    reverse_open: BinaryHeap<Reverse<(isize, usize)>>,
+}
+
+impl PathfindingContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a context whose buffers already fit a honeycomb with the given number of cells
+    pub fn with_capacity(cells: usize) -> Self {
+        let mut context = Self::new();
+        context.begin(cells);
+
+        context
+    }
+
+    /// Returns the path found by the last successful query, or an empty slice if it found none
+    pub fn path(&self) -> &[HexCell] {
+        &self.path
+    }
+
+    /// Prepares the buffers for a new query on a honeycomb with the given number of cells
+    pub(crate) fn begin(&mut self, cells: usize) {
+        self.heap.clear();
+        self.queue.clear();
+        self.path.clear();
+        self.forward_open.clear();
+        self.reverse_open.clear();
+
+        if self.stamps.len() < cells {
+            self.stamps.resize(cells, 0);
+            self.costs.resize(cells, 0);
+            self.came_from.resize(cells, 0);
+        }
+
+        // Stamps left over from the last time the generation had this value would look current,
+        // so they are all cleared once the generation wraps around
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.stamps.fill(0);
+            self.reverse_stamps.fill(0);
+            self.generation = 1;
+        }
+    }
+
+    /// Same as `PathfindingContext::begin()`, also preparing the buffers of the backward search
+    fn begin_bidirectional(&mut self, cells: usize) {
+        self.begin(cells);
+
+        if self.reverse_stamps.len() < cells {
+            self.reverse_stamps.resize(cells, 0);
+            self.reverse_costs.resize(cells, 0);
+            self.goes_to.resize(cells, 0);
+        }
+    }
+
+    pub(crate) fn is_reached(&self, index: usize) -> bool {
+        self.stamps[index] == self.generation
+    }
+
+    pub(crate) fn cost(&self, index: usize) -> Option<usize> {
+        self.is_reached(index).then(|| self.costs[index])
+    }
+
+    pub(crate) fn reach(&mut self, index: usize, cost: usize, came_from: usize) {
+        self.stamps[index] = self.generation;
+        self.costs[index] = cost;
+        self.came_from[index] = came_from;
+    }
+
+    fn reverse_cost(&self, index: usize) -> Option<usize> {
+        (self.reverse_stamps[index] == self.generation).then(|| self.reverse_costs[index])
+    }
+
+    fn reach_reverse(&mut self, index: usize, cost: usize, goes_to: usize) {
+        self.reverse_stamps[index] = self.generation;
+        self.reverse_costs[index] = cost;
+        self.goes_to[index] = goes_to;
+    }
+
+    /// Fills `path` by following `came_from` back from `dest` to `start` in a honeycomb of the given
+    /// size, and returns it
+    pub(crate) fn trace_path(&mut self, size: usize, start: usize, dest: usize) -> &[HexCell] {
+        self.path.clear();
+
+        let mut index = dest;
+        self.path.push(grid_cell(size, index));
+
+        while index != start {
+            index = self.came_from[index];
+            self.path.push(grid_cell(size, index));
+        }
+
+        // path goes from end -> start, we want it the other way around
+        self.path.reverse();
+
+        &self.path
+    }
+
+    /// Fills `path` by following `came_from` back from `meet` to `start` and then `goes_to` on from
+    /// `meet` to `dest`, and returns it
+    fn trace_meeting_path(
+        &mut self,
+        size: usize,
+        start: usize,
+        meet: usize,
+        dest: usize,
+    ) -> &[HexCell] {
+        self.trace_path(size, start, meet);
+
+        let mut index = meet;
+        while index != dest {
+            index = self.goes_to[index];
+            self.path.push(grid_cell(size, index));
+        }
+
+        &self.path
+    }
+}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Same as `Honeycomb::shortest_path`, using the buffers of [`context`] instead of allocating
+    /// new ones. The path is borrowed from [`context`] until its next query.
+    pub fn shortest_path_with<'c>(
+        &self,
+        context: &'c mut PathfindingContext,
+        from: &HexCell,
+        to: &HexCell,
+        filter: fn(curr_val: &T, new_val: &T, edge_val: Option<&E>) -> bool,
+    ) -> Option<&'c [HexCell]> {
+        context.begin(self.grid_len());
+
+        let start = self.index_of(from)?;
+        let dest = self.index_of(to)?;
+
+        if start == dest {
+            context.path.push(*from);
+            return Some(&context.path);
+        }
+
+        context.reach(start, 0, start);
+        context.queue.push_front(start);
+
+        // https://en.wikipedia.org/wiki/Breadth-first_search
+        while let Some(curr) = context.queue.pop_back() {
+            let hex = self.cell_at(curr);
+            let Some(curr_val) = self.data.get(&hex) else {
+                continue;
+            };
+
+            for (neighbor, next, step) in self.steps(hex, false) {
+                let valid = self
+                    .data
+                    .get(&neighbor)
+                    .is_some_and(|new_val| filter(curr_val, new_val, self.step_edge_value(step)));
+
+                if valid && !context.is_reached(next) {
+                    context.reach(next, context.costs[curr] + 1, curr);
+
+                    // We found our target! Time to reconstruct the path
+                    if next == dest {
+                        return Some(context.trace_path(self.size(), start, dest));
+                    }
+
+                    context.queue.push_front(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Same as `Honeycomb::cheapest_path`, using the buffers of [`context`] instead of allocating
+    /// new ones. The path is borrowed from [`context`] until its next query.
+    pub fn cheapest_path_with<'c>(
+        &self,
+        context: &'c mut PathfindingContext,
+        from: &HexCell,
+        to: &HexCell,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+    ) -> Option<&'c [HexCell]> {
+        let limits = SearchLimits::default();
+        let status =
+            self.cheapest_path_limited_with(context, from, to, cost_fn, heuristic, &limits);
+
+        (status == SearchStatus::Complete).then_some(context.path())
+    }
+
+    /// Same as `Honeycomb::cheapest_path_limited`, using the buffers of [`context`] instead of
+    /// allocating new ones. The path, complete or partial, is left in `context.path()`.
+    pub fn cheapest_path_limited_with(
+        &self,
+        context: &mut PathfindingContext,
+        from: &HexCell,
+        to: &HexCell,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+        limits: &SearchLimits<'_>,
+    ) -> SearchStatus {
+        context.begin(self.grid_len());
+
+        let (Some(start), Some(dest)) = (self.index_of(from), self.index_of(to)) else {
+            return SearchStatus::Unreachable;
+        };
+
+        context.reach(start, 0, start);
+        context.heap.push(HexWeight::new(0, start));
+
+        // The reached cell with the lowest estimate of the cost left to the destination, along with
+        // that estimate and the cost of reaching it
+        let mut closest = (start, heuristic(from, to), 0);
+        let mut expansions = 0;
+        let mut limited = false;
+        // The cells left out for costing more than the limit, which only make the search partial
+        // if they are never reached more cheaply
+        let mut over_max_cost = Vec::new();
+
+        // https://en.wikipedia.org/wiki/A*_search_algorithm
+        while let Some(weight) = context.heap.pop() {
+            let curr = weight.index;
+            let hex = self.cell_at(curr);
+
+            // Cells are pushed again whenever a cheaper way into them is found, and the entries
+            // left behind are skipped without counting as expansions
+            if weight.cost > context.costs[curr] + heuristic(&hex, to) {
+                continue;
+            }
+
+            // The destination is only settled once it leaves the work list, since a cheaper edge
+            // into it may still be found before then
+            if curr == dest {
+                context.trace_path(self.size(), start, dest);
+                return SearchStatus::Complete;
+            }
+
+            let out_of_expansions = limits.max_expansions.is_some_and(|max| expansions >= max);
+            if out_of_expansions || limits.cancel.is_some_and(|cancel| cancel()) {
+                limited = true;
+                break;
+            }
+            expansions += 1;
+
+            let Some(old_val) = self.data.get(&hex) else {
+                continue;
+            };
+
+            for (neighbor, next, step) in self.steps(hex, false) {
+                let Some(new_val) = self.data.get(&neighbor) else {
+                    continue;
+                };
+
+                let new_cost =
+                    context.costs[curr] + self.step_cost(old_val, new_val, step, cost_fn);
+
+                if limits.max_cost.is_some_and(|max| new_cost > max) {
+                    over_max_cost.push(next);
+                    continue;
+                }
+
+                let is_cheaper = context.cost(next).is_none_or(|cost| new_cost < cost);
+
+                if is_cheaper {
+                    context.reach(next, new_cost, curr);
+
+                    let estimate = heuristic(&neighbor, to);
+                    if (estimate, new_cost) < (closest.1, closest.2) {
+                        closest = (next, estimate, new_cost);
+                    }
+
+                    context.heap.push(HexWeight::new(new_cost + estimate, next));
+                }
+            }
+        }
+
+        limited |= over_max_cost
+            .into_iter()
+            .any(|index| context.cost(index).is_none());
+        if !limited {
+            return SearchStatus::Unreachable;
+        }
+
+        context.trace_path(self.size(), start, closest.0);
+        SearchStatus::Partial
+    }
+
+    /// Same as `Honeycomb::bidirectional_path`, using the buffers of [`context`] instead of
+    /// allocating new ones. The path is borrowed from [`context`] until its next query.
+    pub fn bidirectional_path_with<'c>(
+        &self,
+        context: &'c mut PathfindingContext,
+        from: &HexCell,
+        to: &HexCell,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+        heuristic: fn(curr: &HexCell, dest: &HexCell) -> usize,
+    ) -> Option<&'c [HexCell]> {
+        context.begin_bidirectional(self.grid_len());
+
+        let start = self.index_of(from)?;
+        let dest = self.index_of(to)?;
+
+        if start == dest {
+            context.path.push(*from);
+            return Some(&context.path);
+        }
+
+        // Both searches order their work lists by the same potential, the average of the estimate
+        // towards the destination and the estimate from the start, taken with opposite signs. Twice
+        // the potential is used so that it stays an integer.
+        let potential = |hex: &HexCell| heuristic(hex, to) as isize - heuristic(from, hex) as isize;
+        let forward_key = |cost: usize, hex: &HexCell| 2 * cost as isize + potential(hex);
+        let reverse_key = |cost: usize, hex: &HexCell| 2 * cost as isize - potential(hex);
+
+        context.reach(start, 0, start);
+        context
+            .forward_open
+            .push(Reverse((forward_key(0, from), start)));
+        context.reach_reverse(dest, 0, dest);
+        context
+            .reverse_open
+            .push(Reverse((reverse_key(0, to), dest)));
+
+        // The cost of the cheapest path found so far, and the cell where its two halves meet
+        let mut best: Option<(usize, usize)> = None;
+
+        // https://en.wikipedia.org/wiki/Bidirectional_search
+        loop {
+            // Entries that were pushed again with a lower cost are out of date
+            while let Some(&Reverse((key, index))) = context.forward_open.peek() {
+                if context
+                    .cost(index)
+                    .is_some_and(|c| key == forward_key(c, &self.cell_at(index)))
+                {
+                    break;
+                }
+                context.forward_open.pop();
+            }
+            while let Some(&Reverse((key, index))) = context.reverse_open.peek() {
+                let hex = &self.cell_at(index);
+                if context
+                    .reverse_cost(index)
+                    .is_some_and(|c| key == reverse_key(c, hex))
+                {
+                    break;
+                }
+                context.reverse_open.pop();
+            }
+
+            let (Some(Reverse((forward, _))), Some(Reverse((reverse, _)))) =
+                (context.forward_open.peek(), context.reverse_open.peek())
+            else {
+                break;
+            };
+
+            // No path through the remaining cells of either work list can be cheaper than this one
+            if let Some((cost, _)) = best {
+                if forward + reverse >= 2 * cost as isize {
+                    break;
+                }
+            }
+
+            let is_forward = forward <= reverse;
+            let Reverse((_, curr)) = if is_forward {
+                context.forward_open.pop()
+            } else {
+                context.reverse_open.pop()
+            }
+            .unwrap();
+
+            let hex = self.cell_at(curr);
+            let Some(curr_val) = self.data.get(&hex) else {
+                continue;
+            };
+
+            for (neighbor, next, step) in self.steps(hex, !is_forward) {
+                let Some(next_val) = self.data.get(&neighbor) else {
+                    continue;
+                };
+
+                // The backward search moves against the direction of travel, so its steps are
+                // costed from the neighbor into the current cell
+                let (new_cost, other_cost) = if is_forward {
+                    let new_cost =
+                        context.costs[curr] + self.step_cost(curr_val, next_val, step, cost_fn);
+                    if context.cost(next).is_some_and(|cost| new_cost >= cost) {
+                        continue;
+                    }
+
+                    context.reach(next, new_cost, curr);
+                    let key = forward_key(new_cost, &neighbor);
+                    context.forward_open.push(Reverse((key, next)));
+
+                    (new_cost, context.reverse_cost(next))
+                } else {
+                    let new_cost = context.reverse_costs[curr]
+                        + self.step_cost(next_val, curr_val, step, cost_fn);
+                    if context
+                        .reverse_cost(next)
+                        .is_some_and(|cost| new_cost >= cost)
+                    {
+                        continue;
+                    }
+
+                    context.reach_reverse(next, new_cost, curr);
+                    let key = reverse_key(new_cost, &neighbor);
+                    context.reverse_open.push(Reverse((key, next)));
+
+                    (new_cost, context.cost(next))
+                };
+
+                if let Some(other_cost) = other_cost {
+                    let cost = new_cost + other_cost;
+                    if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+                        best = Some((cost, next));
+                    }
+                }
+            }
+        }
+
+        let (_, meet) = best?;
+        Some(context.trace_meeting_path(self.size(), start, meet, dest))
+    }
+}
+
+/// Limits on how much work a search may do, for callers that would rather have a partial path in
+/// time than the cheapest one late. Every limit is off by default.
+#[derive(Clone, Copy, Default)]
+pub struct SearchLimits<'a> {
+    /// The most cells the search may expand
+    pub max_expansions: Option<usize>,
+    /// The highest cost a path may have; cells that are more expensive to reach are left out
+    pub max_cost: Option<usize>,
+    /// Called before every expansion, stopping the search as soon as it returns `true`. It can
+    /// borrow whatever it checks, such as the deadline or cancellation flag of a single agent.
+    pub cancel: Option<&'a dyn Fn() -> bool>,
+}
+
+impl fmt::Debug for SearchLimits<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SearchLimits")
+            .field("max_expansions", &self.max_expansions)
+            .field("max_cost", &self.max_cost)
+            .field("cancel", &self.cancel.map(|_| ".."))
+            .finish()
+    }
+}
+
+/// How a search limited by `SearchLimits` ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchStatus {
+    /// The cheapest path to the destination was found
+    Complete,
+    /// A limit was hit first, and the path leads to the reached cell that the heuristic estimates
+    /// to be closest to the destination. A destination that can't be reached at all is reported
+    /// this way as well when the cost limit kept the search from some cells, since telling the two
+    /// apart would take searching past the limit.
+    Partial,
+    /// Every cell that could be reached was explored without reaching the destination, or either
+    /// end lies outside the honeycomb
+    Unreachable,
+}
+
+/// A cell waiting in the work list of a search, identified by its index in the grid
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct HexWeight {
+    pub cost: usize,
+    pub index: usize,
+}
+
+impl HexWeight {
+    pub(crate) fn new(cost: usize, index: usize) -> Self {
+        Self { cost, index }
+    }
+}
+
+impl PartialOrd<Self> for HexWeight {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HexWeight {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::hex_edge::HexEdge;
+    use crate::honeycomb::Honeycomb;
+    use crate::pathfinding::{PathfindingContext, SearchLimits, SearchStatus};
+    use crate::test_utils::{path_cost, random_cell, random_honeycomb};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::cell::Cell;
+    use std::collections::HashMap;
+
+    /// Returns the cost of the cheapest path from `from` to every cell that can be reached from it,
+    /// found by relaxing every step over and over until nothing changes
+    fn brute_force_costs(
+        honeycomb: &Honeycomb<u8>,
+        from: HexCell,
+        cost_fn: fn(&u8, &u8, Option<&()>) -> usize,
+    ) -> HashMap<HexCell, usize> {
+        let mut costs = HashMap::from([(from, 0)]);
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for (hex, cost) in costs.clone() {
+                for neighbor in hex.neighbors() {
+                    let (Some(a), Some(b)) =
+                        (honeycomb.data.get(&hex), honeycomb.data.get(&neighbor))
+                    else {
+                        continue;
+                    };
+
+                    let new_cost = cost + cost_fn(a, b, None);
+                    if costs.get(&neighbor).is_none_or(|c| new_cost < *c) {
+                        costs.insert(neighbor, new_cost);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        costs
+    }
+
+    #[test]
+    fn test_reused_context() {
+        let mut rng = StdRng::seed_from_u64(41);
+        let honeycomb = random_honeycomb(&mut rng, 8, (4, 5), 1..4);
+
+        let cost_fn = |_: &u8, b: &u8, _: Option<&()>| *b as usize;
+        let heuristic = |a: &HexCell, b: &HexCell| a.axial_dist_to(b);
+        let mut context = PathfindingContext::with_capacity(honeycomb.grid.len());
+
+        for _ in 0..100 {
+            let from = random_cell(&mut rng, &honeycomb);
+            let to = random_cell(&mut rng, &honeycomb);
+
+            // Every query on the reused context agrees with a search that shares none of its code
+            let costs = brute_force_costs(&honeycomb, from, cost_fn);
+            let steps = brute_force_costs(&honeycomb, from, |_, _, _| 1);
+
+            let cheapest = honeycomb
+                .cheapest_path_with(&mut context, &from, &to, cost_fn, heuristic)
+                .map(|p| path_cost(&honeycomb, p, cost_fn));
+            assert_eq!(cheapest, costs.get(&to).copied());
+
+            let shortest = honeycomb
+                .shortest_path_with(&mut context, &from, &to, |_, _, _| true)
+                .map(|p| p.len() - 1);
+            assert_eq!(shortest, steps.get(&to).copied());
+            assert_eq!(context.path().len(), shortest.map_or(0, |s| s + 1));
+        }
+    }
+
+    #[test]
+    fn test_changed_grid() {
+        let mut honeycomb = Honeycomb::<u8>::new(4);
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, 1);
+        }
+
+        let from = HexCell::new(-4, 2);
+        let to = HexCell::new(3, -3);
+        let cost_fn = |_: &u8, _: &u8, _: Option<&()>| 1;
+        let heuristic = |a: &HexCell, b: &HexCell| a.axial_dist_to(b);
+
+        // Searches find cells by their coordinates, whatever the caller does to the grid
+        honeycomb.grid.reverse();
+        honeycomb.grid.push(HexCell::new(10, 0));
+        honeycomb.grid.truncate(5);
+
+        let shortest = honeycomb.shortest_path(&from, &to, |_, _, _| true).unwrap();
+        let cheapest = honeycomb
+            .cheapest_path(&from, &to, cost_fn, heuristic)
+            .unwrap();
+        let bidirectional = honeycomb
+            .bidirectional_path(&from, &to, cost_fn, heuristic)
+            .unwrap();
+
+        for path in [shortest, cheapest, bidirectional] {
+            assert_eq!((path.len(), path[0], path[7]), (8, from, to));
+            path_cost(&honeycomb, &path, cost_fn);
+        }
+        assert_eq!(honeycomb.flood_fill(&from, |_, _, _| true).len(), 61);
+    }
+
+    #[test]
+    fn test_bidirectional() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let honeycomb = random_honeycomb(&mut rng, 12, (4, 5), 1..5);
+
+        // Climbing costs more than descending, so that paths differ depending on their direction
+        let cost_fn = |a: &u8, b: &u8, _: Option<&()>| *a as usize + 2 * *b as usize;
+        let heuristic = |a: &HexCell, b: &HexCell| a.axial_dist_to(b) * 3;
+        let mut context = PathfindingContext::new();
+
+        for _ in 0..300 {
+            let from = random_cell(&mut rng, &honeycomb);
+            let to = random_cell(&mut rng, &honeycomb);
+
+            let cheapest = honeycomb.cheapest_path(&from, &to, cost_fn, heuristic);
+            let dijkstra = honeycomb.cheapest_path(&from, &to, cost_fn, |_, _| 0);
+
+            for heuristic in [heuristic, |_: &HexCell, _: &HexCell| 0] {
+                let bidirectional = honeycomb
+                    .bidirectional_path_with(&mut context, &from, &to, cost_fn, heuristic)
+                    .map(|p| p.to_vec());
+                assert_eq!(bidirectional.is_some(), cheapest.is_some());
+
+                if let (Some(bidirectional), Some(cheapest)) = (bidirectional, &cheapest) {
+                    assert_eq!(
+                        (bidirectional[0], *bidirectional.last().unwrap()),
+                        (from, to)
+                    );
+                    assert_eq!(
+                        path_cost(&honeycomb, &bidirectional, cost_fn),
+                        path_cost(&honeycomb, cheapest, cost_fn)
+                    );
+                    assert_eq!(
+                        path_cost(&honeycomb, &bidirectional, cost_fn),
+                        path_cost(&honeycomb, dijkstra.as_ref().unwrap(), cost_fn)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_expansions() {
+        // The direct edge into the third cell is so expensive that the way around it is found
+        // later, leaving a stale entry in the work list that is popped before the destination
+        let cells = [(0, 0), (1, 0), (1, -1), (2, -2)].map(|(q, r)| HexCell::new(q, r));
+        let mut honeycomb = Honeycomb::<(), usize>::new(2);
+        for h in cells {
+            honeycomb.data.insert(h, ());
+        }
+        for (a, b, cost) in [(0, 1, 1), (0, 2, 10), (1, 2, 1), (2, 3, 20)] {
+            let edge = HexEdge::between(&cells[a], &cells[b]).unwrap();
+            honeycomb.edge_data.insert(edge, cost);
+        }
+
+        let cost_fn = |_: &(), _: &(), e: Option<&usize>| *e.unwrap();
+        let heuristic = |_: &HexCell, _: &HexCell| 0;
+
+        // Three cells are expanded before the destination is settled, and no more are counted
+        let complete = vec![cells[0], cells[1], cells[2], cells[3]];
+        for (max_expansions, expected) in [
+            (3, (SearchStatus::Complete, complete)),
+            (2, (SearchStatus::Partial, vec![cells[0]])),
+        ] {
+            let limits = SearchLimits {
+                max_expansions: Some(max_expansions),
+                ..Default::default()
+            };
+            let found =
+                honeycomb.cheapest_path_limited(&cells[0], &cells[3], cost_fn, heuristic, &limits);
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut honeycomb = Honeycomb::<()>::new(6);
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, ());
+        }
+
+        let from = HexCell::new(-5, 0);
+        let to = HexCell::new(5, 0);
+        let cost_fn = |_: &(), _: &(), _: Option<&()>| 1;
+        let heuristic = |a: &HexCell, b: &HexCell| a.axial_dist_to(b);
+
+        let unlimited = SearchLimits::default();
+        let (status, path) =
+            honeycomb.cheapest_path_limited(&from, &to, cost_fn, heuristic, &unlimited);
+        assert_eq!(status, SearchStatus::Complete);
+        assert_eq!(
+            Some(path),
+            honeycomb.cheapest_path(&from, &to, cost_fn, heuristic)
+        );
+
+        // The partial path ends as close to the destination as the cost limit allows
+        let limits = SearchLimits {
+            max_cost: Some(4),
+            ..Default::default()
+        };
+        let (status, path) =
+            honeycomb.cheapest_path_limited(&from, &to, cost_fn, heuristic, &limits);
+        assert_eq!(status, SearchStatus::Partial);
+        assert_eq!((path.len(), path[4]), (5, HexCell::new(-1, 0)));
+
+        let limits = SearchLimits {
+            cancel: Some(&|| true),
+            ..Default::default()
+        };
+        let (status, path) =
+            honeycomb.cheapest_path_limited(&from, &to, cost_fn, heuristic, &limits);
+        assert_eq!((status, path), (SearchStatus::Partial, vec![from]));
+
+        // The cancellation check can keep state of its own, such as a budget for a single agent
+        let checks = Cell::new(0);
+        let cancel = || {
+            checks.set(checks.get() + 1);
+            checks.get() > 3
+        };
+        let limits = SearchLimits {
+            cancel: Some(&cancel),
+            ..Default::default()
+        };
+        let (status, path) =
+            honeycomb.cheapest_path_limited(&from, &to, cost_fn, heuristic, &limits);
+        assert_eq!((status, checks.get()), (SearchStatus::Partial, 4));
+        assert_eq!(path.last().unwrap().axial_dist_to(&to), 7);
+
+        // Walling off the destination makes it unreachable, unless the search gives up first
+        for h in to.neighbors() {
+            honeycomb.data.remove(&h);
+        }
+
+        let (status, path) =
+            honeycomb.cheapest_path_limited(&from, &to, cost_fn, heuristic, &unlimited);
+        assert_eq!((status, path), (SearchStatus::Unreachable, vec![]));
+
+        // A cost limit that leaves out no cell which couldn't be reached more cheaply changes
+        // nothing, while a tighter one can't tell an unreachable destination from a distant one
+        for (max_cost, expected) in [(20, SearchStatus::Unreachable), (4, SearchStatus::Partial)] {
+            let limits = SearchLimits {
+                max_cost: Some(max_cost),
+                ..Default::default()
+            };
+            let (status, _) =
+                honeycomb.cheapest_path_limited(&from, &to, cost_fn, heuristic, &limits);
+            assert_eq!(status, expected);
+        }
+
+        let limits = SearchLimits {
+            max_expansions: Some(30),
+            ..Default::default()
+        };
+        let (status, path) =
+            honeycomb.cheapest_path_limited(&from, &to, cost_fn, heuristic, &limits);
+        assert_eq!(status, SearchStatus::Partial);
+        assert_eq!(path[0], from);
+        assert_eq!(path.last().unwrap().axial_dist_to(&to), 2);
+    }
+
+    #[test]
+    fn test_generation_wrap() {
+        let mut honeycomb = Honeycomb::<()>::new(2);
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, ());
+        }
+
+        let from = HexCell::new(-2, 0);
+        let to = HexCell::new(2, 0);
+        let mut context = PathfindingContext::new();
+
+        // Queries just before and after the generation wraps around still see a clean slate
+        context.generation = u32::MAX - 2;
+        for _ in 0..5 {
+            let path = honeycomb
+                .cheapest_path_with(
+                    &mut context,
+                    &from,
+                    &to,
+                    |_, _, _| 1,
+                    |a, b| a.axial_dist_to(b),
+                )
+                .unwrap();
+            assert_eq!(path.len(), 5);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use crate::hex_cell::HexCell;
+use crate::hex_edge::HexEdge;
+use crate::honeycomb::Honeycomb;
+
+/// A one-way connection between two cells of a honeycomb that need not be adjacent, such as a
+/// teleporter, a staircase or a bridge. Connections both ways take two portals.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Portal {
+    pub from: HexCell,
+    pub to: HexCell,
+    /// The cost of moving through this portal, or `None` to charge whatever the cost function
+    /// charges for moving between the values of both cells with no edge value
+    pub cost: Option<usize>,
+}
+
+impl Portal {
+    pub fn new(from: HexCell, to: HexCell, cost: Option<usize>) -> Self {
+        Self { from, to, cost }
+    }
+}
+
+/// How a step between two cells is taken
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Step {
+    /// Across the edge shared by two adjacent cells
+    Edge(HexEdge),
+    /// Through a portal with the given cost
+    Portal(Option<usize>),
+}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Adds a portal to this honeycomb, replacing any other portal between the same cells in the
+    /// same direction. Returns `false` and leaves the honeycomb unchanged if either end lies outside
+    /// of it.
+    ///
+    /// Searches only find their cheapest paths if their heuristics never overestimate, which
+    /// portals can make heuristics based on the distance between cells do.
+    pub fn add_portal(&mut self, portal: Portal) -> bool {
+        if self.index_of(&portal.from).is_none() || self.index_of(&portal.to).is_none() {
+            return false;
+        }
+
+        self.remove_portal(&portal.from, &portal.to);
+        self.portals_from
+            .entry(portal.from)
+            .or_default()
+            .push(portal);
+        self.portals_into.entry(portal.to).or_default().push(portal);
+
+        true
+    }
+
+    /// Removes the portal from `from` to `to` and returns it, if there is one
+    pub fn remove_portal(&mut self, from: &HexCell, to: &HexCell) -> Option<Portal> {
+        let leaving = self.portals_from.get_mut(from)?;
+        let index = leaving.iter().position(|p| p.to == *to)?;
+        let portal = leaving.remove(index);
+
+        if leaving.is_empty() {
+            self.portals_from.remove(from);
+        }
+
+        if let Some(entering) = self.portals_into.get_mut(to) {
+            entering.retain(|p| p.from != *from);
+            if entering.is_empty() {
+                self.portals_into.remove(to);
+            }
+        }
+
+        Some(portal)
+    }
+
+    /// Returns the portals leading out of the given cell
+    pub fn portals_from(&self, hex: &HexCell) -> &[Portal] {
+        self.portals_from.get(hex).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the portals leading into the given cell
+    pub fn portals_into(&self, hex: &HexCell) -> &[Portal] {
+        self.portals_into.get(hex).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns every portal of this honeycomb
+    pub fn portals(&self) -> impl Iterator<Item = &Portal> {
+        self.portals_from.values().flatten()
+    }
+
+    /// Returns every step out of the given cell, into its neighbors inside the honeycomb and
+    /// through its portals, along with the grid index of the cell stepped into. If [`reverse`],
+    /// returns every step into the given cell instead, along with the cell stepped out of.
+    pub(crate) fn steps(
+        &self,
+        hex: HexCell,
+        reverse: bool,
+    ) -> impl Iterator<Item = (HexCell, usize, Step)> + '_ {
+        let neighbors = hex.neighbor_array().into_iter().enumerate().filter_map(
+            move |(direction, neighbor)| {
+                let index = self.index_of(&neighbor)?;
+                let edge = HexEdge::from_direction(hex, direction);
+
+                Some((neighbor, index, Step::Edge(edge)))
+            },
+        );
+
+        let portals = if reverse {
+            self.portals_into(&hex)
+        } else {
+            self.portals_from(&hex)
+        };
+
+        // Portals are only ever added between cells inside the honeycomb
+        neighbors.chain(portals.iter().map(move |p| {
+            let other = if reverse { p.from } else { p.to };
+            (other, self.index_of(&other).unwrap(), Step::Portal(p.cost))
+        }))
+    }
+
+    /// Returns the value of the edge a step is taken across, if there is one
+    pub(crate) fn step_edge_value(&self, step: Step) -> Option<&E> {
+        match step {
+            Step::Edge(edge) => self.edge_data.get(&edge),
+            Step::Portal(_) => None,
+        }
+    }
+
+    /// Returns the cost of a step from a cell with value [`curr_val`] into one with [`next_val`]
+    pub(crate) fn step_cost(
+        &self,
+        curr_val: &T,
+        next_val: &T,
+        step: Step,
+        cost_fn: fn(curr_val: &T, next_val: &T, edge_val: Option<&E>) -> usize,
+    ) -> usize {
+        match step {
+            Step::Portal(Some(cost)) => cost,
+            _ => cost_fn(curr_val, next_val, self.step_edge_value(step)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::portal::Portal;
+
+    /// Two open halves of a honeycomb, separated by a wall that only a teleporter gets past
+    fn divided() -> (Honeycomb<u8>, Portal) {
+        let mut honeycomb = Honeycomb::<u8>::new(5);
+        for h in honeycomb.grid.clone() {
+            if h.q != 0 {
+                honeycomb.data.insert(h, 1);
+            }
+        }
+
+        let portal = Portal::new(HexCell::new(-2, 0), HexCell::new(3, 1), Some(4));
+        assert!(honeycomb.add_portal(portal));
+
+        (honeycomb, portal)
+    }
+
+    #[test]
+    fn test_add_remove() {
+        let (mut honeycomb, portal) = divided();
+        assert_eq!(honeycomb.portals_from(&portal.from), &[portal]);
+        assert_eq!(honeycomb.portals_into(&portal.to), &[portal]);
+        assert!(honeycomb.neighbors_of(portal.from).contains(&portal.to));
+        assert!(!honeycomb.neighbors_of(portal.to).contains(&portal.from));
+
+        // Adding a portal between the same cells replaces the old one
+        let cheaper = Portal::new(portal.from, portal.to, None);
+        assert!(honeycomb.add_portal(cheaper));
+        assert_eq!(honeycomb.portals().collect::<Vec<_>>(), vec![&cheaper]);
+
+        assert!(!honeycomb.add_portal(Portal::new(portal.from, HexCell::new(6, 0), None)));
+        assert_eq!(
+            honeycomb.remove_portal(&portal.from, &portal.to),
+            Some(cheaper)
+        );
+        assert_eq!(honeycomb.remove_portal(&portal.from, &portal.to), None);
+        assert!(honeycomb.portals_from(&portal.from).is_empty());
+        assert!(honeycomb.portals_into(&portal.to).is_empty());
+    }
+
+    #[test]
+    fn test_searches() {
+        let (mut honeycomb, portal) = divided();
+        let from = HexCell::new(-4, 2);
+        let to = HexCell::new(4, -1);
+        let cost_fn = |_: &u8, _: &u8, _: Option<&()>| 1;
+        let heuristic = |_: &HexCell, _: &HexCell| 0;
+
+        let shortest = honeycomb.shortest_path(&from, &to, |_, _, _| true).unwrap();
+        let cheapest = honeycomb
+            .cheapest_path(&from, &to, cost_fn, heuristic)
+            .unwrap();
+        let bidirectional = honeycomb
+            .bidirectional_path(&from, &to, cost_fn, heuristic)
+            .unwrap();
+
+        for path in [&shortest, &cheapest, &bidirectional] {
+            let jump = path.iter().position(|h| *h == portal.from).unwrap();
+            assert_eq!(path[jump + 1], portal.to);
+        }
+
+        // Two steps to the portal, one through it, and two more from there
+        assert_eq!(shortest.len(), 6);
+        assert_eq!(cheapest.len(), 6);
+        assert_eq!(bidirectional.len(), 6);
+
+        // Steps through the portal have no direction, but still lead on to the next cell
+        let field = honeycomb.flow_field(&[to], cost_fn);
+        assert_eq!(field.cost_at(&from), Some(2 + 4 + 2));
+        assert_eq!(field.cost_at(&portal.from), Some(4 + 2));
+        assert_eq!(field.direction_at(&portal.from), None);
+        assert_eq!(field.next_cell(&portal.from), Some(portal.to));
+
+        let reachable = honeycomb.flood_fill(&from, |_, _, _| true);
+        assert_eq!(reachable.len(), honeycomb.data.len());
+
+        // Portals only lead one way
+        assert!(honeycomb
+            .shortest_path(&to, &from, |_, _, _| true)
+            .is_none());
+
+        honeycomb.remove_portal(&portal.from, &portal.to);
+        assert!(honeycomb
+            .cheapest_path(&from, &to, cost_fn, heuristic)
+            .is_none());
+        assert!(honeycomb.flood_fill(&from, |_, _, _| true).len() < honeycomb.data.len());
+    }
+}
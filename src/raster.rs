@@ -0,0 +1,312 @@
+use crate::cartesian_point::CartesianPoint;
+use crate::hex_cell::HexCell;
+use crate::honeycomb::Honeycomb;
+use crate::layout::Layout;
+use std::io::Write;
+
+/// An image with 8-bit RGBA pixels stored row by row, starting from the top left
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Returns a new image filled with the given color
+    pub fn new(width: u32, height: u32, color: [u8; 4]) -> Self {
+        let pixels = color.repeat(pixel_count(width, height));
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Returns the color of the pixel in column `x` and row `y`
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    /// Writes this image to [`writer`] as a PNG file
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+
+        writer.finish()
+    }
+}
+
+/// How `Honeycomb::rasterize` draws everything other than the cells themselves
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RasterOptions {
+    pub background: [u8; 4],
+    /// Color of the lines drawn along the edges of every cell, if any
+    pub stroke: Option<[u8; 4]>,
+    /// Width of the lines drawn along the edges of every cell, in pixels
+    pub stroke_width: f32,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self {
+            background: [0, 0, 0, 0],
+            stroke: Some([0, 0, 0, 255]),
+            stroke_width: 1.0,
+        }
+    }
+}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Draws every cell of this honeycomb into a `width` by `height` image, positioned by
+    /// [`layout`] with one surface unit per pixel.
+    ///
+    /// [`color_fn`] takes in a cell and its value, if it has one, and returns the RGBA color the cell
+    /// is filled with. The borders of cells and the lines along their edges are anti-aliased.
+    pub fn rasterize(
+        &self,
+        layout: &Layout,
+        width: u32,
+        height: u32,
+        color_fn: fn(hex: &HexCell, val: Option<&T>) -> [u8; 4],
+        options: &RasterOptions,
+    ) -> Image {
+        // Premultiplied color and total coverage of every pixel. Coverage is accumulated rather than
+        // blended so that neighboring cells each covering half of a pixel don't let the background
+        // bleed through along their shared edge
+        let mut color = vec![[0f32; 4]; pixel_count(width, height)];
+        let mut coverage = vec![0f32; pixel_count(width, height)];
+
+        for h in self.grid.iter() {
+            let fill = premultiply(color_fn(h, self.data.get(h)));
+            let corners = layout.hex_corners(h);
+            let center = layout.hex_center(h);
+
+            // The outward normal of every side of the hexagon
+            let sides = (0..6)
+                .map(|i| {
+                    let (a, b) = (corners[i], corners[(i + 1) % 6]);
+                    let (dx, dy) = (b.x - a.x, b.y - a.y);
+                    let len = (dx * dx + dy * dy).sqrt();
+                    let mut normal = CartesianPoint::new(dy / len, -dx / len);
+
+                    if (center.x - a.x) * normal.x + (center.y - a.y) * normal.y > 0.0 {
+                        normal = CartesianPoint::new(-normal.x, -normal.y);
+                    }
+
+                    (a, normal)
+                })
+                .collect::<Vec<_>>();
+
+            for (x, y) in pixels_around(&corners, 1.0, width, height) {
+                let p = CartesianPoint::new(x as f32 + 0.5, y as f32 + 0.5);
+
+                let dist = sides
+                    .iter()
+                    .map(|(a, n)| (p.x - a.x) * n.x + (p.y - a.y) * n.y)
+                    .fold(f32::MIN, f32::max);
+
+                let cov = (0.5 - dist).clamp(0.0, 1.0);
+                if cov > 0.0 {
+                    let i = y as usize * width as usize + x as usize;
+
+                    for c in 0..4 {
+                        color[i][c] += fill[c] * cov;
+                    }
+                    coverage[i] += cov;
+                }
+            }
+        }
+
+        let background = premultiply(options.background);
+        for (px, cov) in color.iter_mut().zip(coverage) {
+            if cov > 1.0 {
+                px.iter_mut().for_each(|c| *c /= cov);
+            }
+
+            let alpha = px[3];
+            for c in 0..4 {
+                px[c] += background[c] * (1.0 - alpha);
+            }
+        }
+
+        if let Some(stroke) = options.stroke {
+            let stroke = premultiply(stroke);
+            let half_width = options.stroke_width / 2.0;
+
+            // Each edge is drawn once, so shared edges aren't blended twice
+            for edge in self.edges() {
+                let [a, b] = edge.vertex_locations().map(|p| layout.world_to_surface(p));
+
+                for (x, y) in pixels_around(&[a, b], half_width + 1.0, width, height) {
+                    let p = CartesianPoint::new(x as f32 + 0.5, y as f32 + 0.5);
+                    let cov = (half_width + 0.5 - distance_to_segment(p, a, b)).clamp(0.0, 1.0);
+
+                    if cov > 0.0 {
+                        let px = &mut color[y as usize * width as usize + x as usize];
+                        let alpha = stroke[3] * cov;
+
+                        for c in 0..4 {
+                            px[c] = stroke[c] * cov + px[c] * (1.0 - alpha);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut image = Image::new(width, height, [0, 0, 0, 0]);
+        for (i, px) in color.iter().enumerate() {
+            let alpha = px[3];
+
+            if alpha > 0.0 {
+                let straight = [px[0] / alpha, px[1] / alpha, px[2] / alpha, alpha];
+
+                for (out, c) in image.pixels[i * 4..i * 4 + 4].iter_mut().zip(straight) {
+                    *out = (c * 255.0).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+
+        image
+    }
+}
+
+/// Returns the number of pixels in a `width` by `height` image, without overflowing `u32` on the
+/// way
+fn pixel_count(width: u32, height: u32) -> usize {
+    (width as usize)
+        .checked_mul(height as usize)
+        .expect("image has more pixels than fit in memory")
+}
+
+/// Converts an RGBA color to premultiplied components between 0 and 1
+fn premultiply(color: [u8; 4]) -> [f32; 4] {
+    let alpha = color[3] as f32 / 255.0;
+
+    [
+        color[0] as f32 / 255.0 * alpha,
+        color[1] as f32 / 255.0 * alpha,
+        color[2] as f32 / 255.0 * alpha,
+        alpha,
+    ]
+}
+
+/// Returns every pixel of the image within [`margin`] pixels of the bounding box of [`points`]
+fn pixels_around(
+    points: &[CartesianPoint],
+    margin: f32,
+    width: u32,
+    height: u32,
+) -> impl Iterator<Item = (u32, u32)> {
+    let min_x = points.iter().map(|p| p.x).fold(f32::MAX, f32::min) - margin;
+    let max_x = points.iter().map(|p| p.x).fold(f32::MIN, f32::max) + margin;
+    let min_y = points.iter().map(|p| p.y).fold(f32::MAX, f32::min) - margin;
+    let max_y = points.iter().map(|p| p.y).fold(f32::MIN, f32::max) + margin;
+
+    let x1 = min_x.floor().max(0.0) as u32;
+    let x2 = (max_x.ceil().max(0.0) as u32).min(width);
+    let y1 = min_y.floor().max(0.0) as u32;
+    let y2 = (max_y.ceil().max(0.0) as u32).min(height);
+
+    (y1..y2).flat_map(move |y| (x1..x2).map(move |x| (x, y)))
+}
+
+fn distance_to_segment(p: CartesianPoint, a: CartesianPoint, b: CartesianPoint) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+    let (cx, cy) = (a.x + t * dx, a.y + t * dy);
+
+    ((p.x - cx).powi(2) + (p.y - cy).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::layout::Layout;
+    use crate::raster::{pixel_count, RasterOptions};
+
+    #[test]
+    fn test_rasterize() {
+        let mut honeycomb = Honeycomb::<bool>::new(1);
+        honeycomb.data.insert(HexCell::origin(), true);
+
+        let layout = Layout::fit(&honeycomb, 64.0, 64.0, 2.0);
+        let options = RasterOptions {
+            background: [255, 255, 255, 255],
+            stroke_width: 3.0,
+            ..RasterOptions::default()
+        };
+        let image = honeycomb.rasterize(
+            &layout,
+            64,
+            64,
+            |_, val| {
+                if val.is_some() {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 255]
+                }
+            },
+            &options,
+        );
+
+        assert_eq!(image.pixels.len(), 64 * 64 * 4);
+        assert_eq!(image.pixel(32, 32), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(0, 0), [255, 255, 255, 255]);
+
+        let east = layout.hex_center(&HexCell::new(1, 0));
+        assert_eq!(image.pixel(east.x as u32, east.y as u32), [0, 0, 255, 255]);
+
+        // The edge between the center and its eastern neighbor is stroked, with anti-aliasing
+        let edge = layout.world_to_surface(HexCell::origin().edges()[0].world_location());
+        let stroked = image.pixel(edge.x as u32, edge.y as u32);
+        assert!(stroked[0] < 128 && stroked[2] < 128);
+
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_pixel_count() {
+        // The product of two u32 dimensions doesn't fit in a u32
+        assert_eq!(pixel_count(70_000, 70_000), 4_900_000_000);
+    }
+
+    #[test]
+    fn test_seams() {
+        let mut honeycomb = Honeycomb::<()>::new(3);
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, ());
+        }
+
+        let layout = Layout::fit(&honeycomb, 100.0, 100.0, 0.0);
+        let options = RasterOptions {
+            background: [0, 0, 0, 255],
+            stroke: None,
+            stroke_width: 0.0,
+        };
+        let image = honeycomb.rasterize(&layout, 100, 100, |_, _| [255, 255, 255, 255], &options);
+
+        // Without strokes, a uniform map is drawn without any background showing between cells
+        let center = layout.hex_center(&HexCell::origin());
+        for dx in -20..20 {
+            let px = image.pixel((center.x as i32 + dx) as u32, center.y as u32);
+            assert_eq!(px, [255, 255, 255, 255]);
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use crate::cartesian_point::CartesianPoint;
+use crate::hex_cell::HexCell;
+use crate::honeycomb::Honeycomb;
+
+/// How close two crossings along a line have to be to count as the same point
+const EPSILON: f64 = 1e-9;
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Pulls the given path taut, returning the world locations of the cells it still has to turn
+    /// at. Every leg of the result is a straight line through walkable cells only, so following it
+    /// never cuts across a cell that the path itself avoided.
+    ///
+    /// Steps between cells that aren't adjacent go through portals. Both ends of such a step are
+    /// kept as corners, and the path is pulled taut separately on either side of it, so the only
+    /// legs that leave walkable cells are the jumps through portals themselves.
+    ///
+    /// [`walkable`] takes in a cell's value and returns whether it may be crossed. Cells without a
+    /// value are never walkable, and every cell of the path is assumed to be.
+    pub fn smooth_path(
+        &self,
+        path: &[HexCell],
+        walkable: fn(val: &T) -> bool,
+    ) -> Vec<CartesianPoint> {
+        let Some(first) = path.first() else {
+            return Vec::new();
+        };
+
+        let mut corners = vec![first.world_location()];
+        let mut anchor = 0;
+
+        let adjacent = |i: usize| path[i].axial_dist_to(&path[i + 1]) == 1;
+
+        // Skip ahead to the last cell that can still be seen from the previous corner, without
+        // carrying the line past a portal
+        while anchor + 1 < path.len() {
+            let mut next = anchor + 1;
+            while adjacent(anchor)
+                && next + 1 < path.len()
+                && adjacent(next)
+                && self.line_of_sight(&path[anchor], &path[next + 1], walkable)
+            {
+                next += 1;
+            }
+
+            corners.push(path[next].world_location());
+            anchor = next;
+        }
+
+        corners
+    }
+
+    /// Returns whether the straight line between the centers of `from` and `to` stays within
+    /// walkable cells, bounded by their `HexCell::vertex_locations()`. Lines running exactly
+    /// through a vertex or along an edge count as crossing every cell they touch.
+    ///
+    /// [`walkable`] is the same as for `Honeycomb::smooth_path`.
+    pub fn line_of_sight(
+        &self,
+        from: &HexCell,
+        to: &HexCell,
+        walkable: fn(val: &T) -> bool,
+    ) -> bool {
+        let is_walkable = |hex: &HexCell| {
+            self.index_of(hex).is_some() && self.data.get(hex).is_some_and(walkable)
+        };
+
+        if !is_walkable(from) || !is_walkable(to) {
+            return false;
+        }
+
+        let start = from.world_point::<f64>();
+        let line = to.world_point::<f64>() - start;
+
+        let mut hex = *from;
+        let mut steps = 0;
+
+        while hex != *to {
+            // Cells are the regions closer to their own center than to any other, so the line
+            // leaves this cell where it first becomes closer to one of its neighbors
+            let center = hex.world_point::<f64>() - start;
+            let exits = hex.neighbor_array().map(|neighbor| {
+                let offset = neighbor.world_point::<f64>() - hex.world_point::<f64>();
+                let approach = line.dot(&offset);
+
+                (approach > EPSILON)
+                    .then(|| (offset.dot(&offset) / 2.0 + center.dot(&offset)) / approach)
+            });
+
+            let Some(exit) = exits.iter().flatten().copied().reduce(f64::min) else {
+                return false;
+            };
+
+            // Every cell the line touches on its way out has to be walkable, and the line goes on
+            // into the one whose center is closest just past the exit
+            let past_exit = start + line * (exit + EPSILON.sqrt());
+            let mut next = None;
+
+            for (neighbor, neighbor_exit) in hex.neighbor_array().iter().zip(exits) {
+                if neighbor_exit.is_none_or(|t| t - exit > EPSILON) {
+                    continue;
+                }
+                if !is_walkable(neighbor) {
+                    return false;
+                }
+
+                let distance = neighbor.world_point::<f64>().distance(&past_exit);
+                if next.is_none_or(|(_, d)| distance < d) {
+                    next = Some((*neighbor, distance));
+                }
+            }
+
+            // A line spanning n steps crosses at most 2n cells, so going on any longer would mean
+            // rounding has led the walk astray
+            steps += 1;
+            if steps > 2 * from.axial_dist_to(to) + 2 {
+                return false;
+            }
+
+            hex = next.unwrap().0;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartesian_point::CartesianPoint;
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::test_utils::portal_honeycomb;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn walkable(val: &bool) -> bool {
+        *val
+    }
+
+    fn open_honeycomb() -> Honeycomb<bool> {
+        let mut honeycomb = Honeycomb::<bool>::new(8);
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, true);
+        }
+
+        honeycomb
+    }
+
+    /// Checks that every leg of the given polyline stays within walkable cells
+    fn assert_walkable(honeycomb: &Honeycomb<bool>, corners: &[CartesianPoint]) {
+        for leg in corners.windows(2) {
+            for i in 0..=1000 {
+                let point = leg[0].lerp(&leg[1], i as f32 / 1000.0);
+                let hex = HexCell::from_world(point);
+                assert_eq!(
+                    honeycomb.data.get(&hex),
+                    Some(&true),
+                    "{point:?} lies on {hex:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_field() {
+        let honeycomb = open_honeycomb();
+        let from = HexCell::new(-5, 1);
+        let to = HexCell::new(4, 2);
+
+        let path = honeycomb
+            .cheapest_path(&from, &to, |_, _, _| 1, |a, b| a.axial_dist_to(b))
+            .unwrap();
+        let corners = honeycomb.smooth_path(&path, walkable);
+
+        assert_eq!(corners, vec![from.world_location(), to.world_location()]);
+        assert_eq!(
+            honeycomb.smooth_path(&[from], walkable),
+            vec![from.world_location()]
+        );
+        assert!(honeycomb.smooth_path(&[], walkable).is_empty());
+    }
+
+    #[test]
+    fn test_around_wall() {
+        let mut honeycomb = open_honeycomb();
+        for r in -8..=4 {
+            honeycomb.data.insert(HexCell::new(0, r), false);
+        }
+
+        let from = HexCell::new(-4, 0);
+        let to = HexCell::new(4, -2);
+        let path = honeycomb
+            .shortest_path(&from, &to, |_, walkable, _| *walkable)
+            .unwrap();
+        let corners = honeycomb.smooth_path(&path, walkable);
+
+        assert!(corners.len() >= 3 && corners.len() < path.len());
+        assert_eq!(corners[0], from.world_location());
+        assert_eq!(corners[corners.len() - 1], to.world_location());
+        assert_walkable(&honeycomb, &corners);
+    }
+
+    #[test]
+    fn test_random_obstacles() {
+        let mut rng = StdRng::seed_from_u64(49);
+
+        for _ in 0..20 {
+            let mut honeycomb = open_honeycomb();
+            for h in honeycomb.grid.clone() {
+                honeycomb.data.insert(h, rng.gen_ratio(3, 4));
+            }
+
+            for _ in 0..10 {
+                let from = honeycomb.grid[rng.gen_range(0..honeycomb.grid.len())];
+                let to = honeycomb.grid[rng.gen_range(0..honeycomb.grid.len())];
+
+                if let Some(path) = honeycomb.shortest_path(&from, &to, |a, b, _| *a && *b) {
+                    assert_walkable(&honeycomb, &honeycomb.smooth_path(&path, walkable));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        let mut honeycomb = open_honeycomb();
+        let from = HexCell::origin();
+
+        // This line runs along the edge between two cells, touching both
+        let to = HexCell::new(1, 1);
+        assert!(honeycomb.line_of_sight(&from, &to, walkable));
+
+        honeycomb.data.insert(HexCell::new(0, 1), false);
+        assert!(!honeycomb.line_of_sight(&from, &to, walkable));
+        assert!(!honeycomb.line_of_sight(&to, &from, walkable));
+
+        assert!(honeycomb.line_of_sight(&from, &HexCell::new(4, 0), walkable));
+        assert!(!honeycomb.line_of_sight(&from, &HexCell::new(0, 4), walkable));
+        assert!(!honeycomb.line_of_sight(&from, &HexCell::new(9, 0), walkable));
+    }
+
+    #[test]
+    fn test_portals() {
+        let mut honeycomb = portal_honeycomb();
+        let from = HexCell::new(-2, 3);
+        let to = HexCell::new(6, -3);
+        let cost_fn = |_: &u8, _: &u8, _: Option<&()>| 1;
+
+        let path = honeycomb
+            .cheapest_path(&from, &to, cost_fn, |_, _| 0)
+            .unwrap();
+        let jump = path
+            .windows(2)
+            .position(|step| step[0].axial_dist_to(&step[1]) > 1)
+            .unwrap();
+        assert_eq!(
+            path[jump..jump + 2],
+            [HexCell::new(2, 1), HexCell::new(4, 1)]
+        );
+
+        // Even once the wall is gone, either side of the portal is smoothed on its own and both of
+        // its ends stay corners
+        for h in honeycomb.grid.clone() {
+            honeycomb.data.insert(h, 1);
+        }
+
+        let corners = honeycomb.smooth_path(&path, |_| true);
+        let mut separately = honeycomb.smooth_path(&path[..=jump], |_| true);
+        separately.extend(honeycomb.smooth_path(&path[jump + 1..], |_| true));
+        assert_eq!(corners, separately);
+        assert!(corners.contains(&HexCell::new(2, 1).world_location()));
+        assert!(corners.contains(&HexCell::new(4, 1).world_location()));
+    }
+}
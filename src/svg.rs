@@ -0,0 +1,235 @@
+use crate::cartesian_point::CartesianPoint;
+use crate::hex_cell::HexCell;
+use crate::honeycomb::Honeycomb;
+use crate::layout::Layout;
+use std::fmt::Write;
+
+/// How a single cell is drawn by `Honeycomb::to_svg`. Colors are any valid SVG paint, such as
+/// `"red"` or `"#ff8800"`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellStyle {
+    pub fill: Option<String>,
+    pub stroke: Option<String>,
+    pub stroke_width: f32,
+    /// Text drawn in the center of the cell
+    pub label: Option<String>,
+}
+
+impl CellStyle {
+    pub fn new(fill: &str) -> Self {
+        Self {
+            fill: Some(fill.to_string()),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for CellStyle {
+    fn default() -> Self {
+        Self {
+            fill: None,
+            stroke: Some("black".to_string()),
+            stroke_width: 1.0,
+            label: None,
+        }
+    }
+}
+
+/// A line drawn through the centers of a sequence of cells, such as the result of a pathfinding
+/// query
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathOverlay {
+    pub path: Vec<HexCell>,
+    pub stroke: String,
+    pub stroke_width: f32,
+}
+
+impl PathOverlay {
+    pub fn new(path: Vec<HexCell>, stroke: &str) -> Self {
+        Self {
+            path,
+            stroke: stroke.to_string(),
+            stroke_width: 3.0,
+        }
+    }
+}
+
+/// Additional content for `Honeycomb::to_svg_with_options`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SvgOptions {
+    /// Paint used for the whole background of the document
+    pub background: Option<String>,
+    /// Paths drawn on top of the cells, in order
+    pub paths: Vec<PathOverlay>,
+    /// Whether to write the `q, r` coordinates of every cell underneath its label
+    pub coordinate_labels: bool,
+}
+
+impl<T, E, V> Honeycomb<T, E, V> {
+    /// Returns an SVG document drawing every cell of this honeycomb, positioned by [`layout`] and
+    /// styled by [`style_fn`].
+    ///
+    /// [`style_fn`] takes in a cell and its value, if it has one, and returns how the cell should be
+    /// drawn.
+    pub fn to_svg(
+        &self,
+        layout: &Layout,
+        style_fn: fn(hex: &HexCell, val: Option<&T>) -> CellStyle,
+    ) -> String {
+        self.to_svg_with_options(layout, style_fn, &SvgOptions::default())
+    }
+
+    /// Same as `Honeycomb::to_svg`, but additionally draws the path overlays and labels described
+    /// by [`options`]
+    pub fn to_svg_with_options(
+        &self,
+        layout: &Layout,
+        style_fn: fn(hex: &HexCell, val: Option<&T>) -> CellStyle,
+        options: &SvgOptions,
+    ) -> String {
+        // The document covers the surface from the origin to the far side of the honeycomb, leaving
+        // the same space on the far side as the layout leaves on the near side
+        let (min, max) = self.world_bounds();
+        let (a, b) = (layout.world_to_surface(min), layout.world_to_surface(max));
+        let (near, far) = (
+            CartesianPoint::new(a.x.min(b.x), a.y.min(b.y)),
+            CartesianPoint::new(a.x.max(b.x), a.y.max(b.y)),
+        );
+        let (x, y) = (near.x.min(0.0), near.y.min(0.0));
+        let (width, height) = (far.x + near.x.max(0.0) - x, far.y + near.y.max(0.0) - y);
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            width, height, x, y, width, height
+        );
+
+        if let Some(background) = &options.background {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                x,
+                y,
+                width,
+                height,
+                escape(background)
+            );
+        }
+
+        let font_size = layout.scale / 2.0;
+
+        for h in self.grid.iter() {
+            let style = style_fn(h, self.data.get(h));
+
+            let _ = writeln!(
+                svg,
+                r#"<polygon points="{}" fill="{}" stroke="{}" stroke-width="{}"/>"#,
+                points(&layout.hex_corners(h)),
+                escape(style.fill.as_deref().unwrap_or("none")),
+                escape(style.stroke.as_deref().unwrap_or("none")),
+                style.stroke_width
+            );
+
+            let center = layout.hex_center(h);
+            let mut lines = Vec::new();
+            if let Some(label) = &style.label {
+                lines.push(label.clone());
+            }
+            if options.coordinate_labels {
+                lines.push(format!("{}, {}", h.q, h.r));
+            }
+
+            for (i, line) in lines.iter().enumerate() {
+                let offset = (i as f32 - (lines.len() - 1) as f32 / 2.0) * font_size;
+
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{}" y="{}" font-size="{}" text-anchor="middle" dominant-baseline="middle">{}</text>"#,
+                    center.x,
+                    center.y + offset,
+                    font_size,
+                    escape(line)
+                );
+            }
+        }
+
+        for overlay in options.paths.iter() {
+            let centers = overlay
+                .path
+                .iter()
+                .map(|h| layout.hex_center(h))
+                .collect::<Vec<_>>();
+
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                points(&centers),
+                escape(&overlay.stroke),
+                overlay.stroke_width
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn points(points: &[CartesianPoint]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{},{}", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::layout::Layout;
+    use crate::svg::{CellStyle, PathOverlay, SvgOptions};
+
+    #[test]
+    fn test_svg() {
+        let mut honeycomb = Honeycomb::<u8>::new(2);
+        honeycomb.data.insert(HexCell::origin(), 3);
+
+        let layout = Layout::fit(&honeycomb, 400.0, 400.0, 5.0);
+        let options = SvgOptions {
+            paths: vec![PathOverlay::new(
+                vec![HexCell::origin(), HexCell::new(1, 0)],
+                "blue",
+            )],
+            coordinate_labels: true,
+            ..SvgOptions::default()
+        };
+
+        let svg = honeycomb.to_svg_with_options(
+            &layout,
+            |_, val| match val {
+                Some(v) => CellStyle {
+                    label: Some(format!("<{}>", v)),
+                    ..CellStyle::new("red")
+                },
+                None => CellStyle::default(),
+            },
+            &options,
+        );
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<polygon").count(), honeycomb.grid.len());
+        assert_eq!(svg.matches(r#"fill="red""#).count(), 1);
+        assert_eq!(svg.matches("<polyline").count(), 1);
+        assert!(svg.contains("&lt;3&gt;"));
+        assert!(svg.contains(">-1, 2</text>"));
+    }
+}
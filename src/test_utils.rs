@@ -0,0 +1,60 @@
+//! Helpers shared by the tests of the pathfinding modules
+
+use crate::hex_cell::HexCell;
+use crate::honeycomb::Honeycomb;
+use crate::portal::Portal;
+use rand::Rng;
+use std::ops::Range;
+
+/// Returns a honeycomb of the given size where every cell has a random value from [`values`] with a
+/// probability of `numerator / denominator`, and no value otherwise
+pub(crate) fn random_honeycomb(
+    rng: &mut impl Rng,
+    size: usize,
+    (numerator, denominator): (u32, u32),
+    values: Range<u8>,
+) -> Honeycomb<u8> {
+    let mut honeycomb = Honeycomb::<u8>::new(size);
+    for h in honeycomb.grid.clone() {
+        if rng.gen_ratio(numerator, denominator) {
+            honeycomb.data.insert(h, rng.gen_range(values.clone()));
+        }
+    }
+
+    honeycomb
+}
+
+/// Returns a random cell of the honeycomb, whether it has a value or not
+pub(crate) fn random_cell<T, E, V>(rng: &mut impl Rng, honeycomb: &Honeycomb<T, E, V>) -> HexCell {
+    honeycomb.grid[rng.gen_range(0..honeycomb.grid.len())]
+}
+
+/// Returns the cost of moving along the given path, checking that every step is legal
+pub(crate) fn path_cost(
+    honeycomb: &Honeycomb<u8>,
+    path: &[HexCell],
+    cost_fn: fn(&u8, &u8, Option<&()>) -> usize,
+) -> usize {
+    path.windows(2)
+        .map(|step| {
+            assert_eq!(step[0].axial_dist_to(&step[1]), 1);
+            cost_fn(&honeycomb.data[&step[0]], &honeycomb.data[&step[1]], None)
+        })
+        .sum()
+}
+
+/// Returns a honeycomb split in two by an empty column, which a portal from `(2, 1)` to `(4, 1)`
+/// only gets across one way
+pub(crate) fn portal_honeycomb() -> Honeycomb<u8> {
+    let mut honeycomb = Honeycomb::<u8>::new(8);
+    for h in honeycomb.grid.clone() {
+        if h.q != 3 {
+            honeycomb.data.insert(h, 1);
+        }
+    }
+
+    let portal = Portal::new(HexCell::new(2, 1), HexCell::new(4, 1), None);
+    assert!(honeycomb.add_portal(portal));
+
+    honeycomb
+}
@@ -0,0 +1,258 @@
+use crate::cartesian_point::{CartesianPoint, Float};
+use crate::hex_cell::HexCell;
+
+/// A view of world space on a screen, used to convert between screen pixels, world locations and
+/// hexes.
+///
+/// The screen has its origin in the top left corner with `y` pointing down, while world space has
+/// `y` pointing up, so a cell with a larger world `y` is drawn higher up on the screen. Use `f64`
+/// coordinates to pick cells precisely far from the origin.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Viewport<F = f32> {
+    /// The world location shown in the center of the screen
+    pub center: CartesianPoint<F>,
+    /// The number of pixels per world unit
+    pub zoom: F,
+    /// How far the world is rotated counter-clockwise on the screen, in radians
+    pub rotation: F,
+    /// The width of the screen in pixels
+    pub width: F,
+    /// The height of the screen in pixels
+    pub height: F,
+}
+
+impl<F: Float> Viewport<F> {
+    /// Returns a new viewport centered on the world origin, with no rotation
+    pub fn new(width: F, height: F, zoom: F) -> Self {
+        Self {
+            center: CartesianPoint::new(F::ZERO, F::ZERO),
+            zoom,
+            rotation: F::ZERO,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the screen location of the given world location
+    pub fn world_to_screen(&self, point: CartesianPoint<F>) -> CartesianPoint<F> {
+        let (sin, cos) = self.rotation.sin_cos();
+        let two = F::from_f64(2.0);
+        let rel = point - self.center;
+
+        let x = rel.x * cos - rel.y * sin;
+        let y = rel.x * sin + rel.y * cos;
+
+        CartesianPoint::new(
+            self.width / two + x * self.zoom,
+            self.height / two - y * self.zoom,
+        )
+    }
+
+    /// Returns the world location of the given screen location
+    pub fn screen_to_world(&self, point: CartesianPoint<F>) -> CartesianPoint<F> {
+        let (sin, cos) = self.rotation.sin_cos();
+        let two = F::from_f64(2.0);
+
+        let x = (point.x - self.width / two) / self.zoom;
+        let y = (self.height / two - point.y) / self.zoom;
+
+        self.center + CartesianPoint::new(x * cos + y * sin, y * cos - x * sin)
+    }
+
+    /// Returns the screen location of the center of the given hex
+    pub fn hex_to_screen(&self, hex: &HexCell) -> CartesianPoint<F> {
+        self.world_to_screen(hex.world_point())
+    }
+
+    /// Returns the hex under the given screen location. Use `Honeycomb::world_to_hex` with
+    /// `Viewport::screen_to_world` instead to only accept hexes inside a honeycomb.
+    pub fn screen_to_hex(&self, point: CartesianPoint<F>) -> HexCell {
+        HexCell::from_world(self.screen_to_world(point))
+    }
+
+    /// Moves the view so that the world follows the cursor as it is dragged by the given number of
+    /// pixels
+    pub fn pan(&mut self, dx: F, dy: F) {
+        let origin = self.screen_to_world(CartesianPoint::new(F::ZERO, F::ZERO));
+        let moved = self.screen_to_world(CartesianPoint::new(dx, dy));
+
+        self.center -= moved - origin;
+    }
+
+    /// Multiplies the zoom by [`factor`], keeping the world location under the given screen
+    /// location in place
+    pub fn zoom_at(&mut self, point: CartesianPoint<F>, factor: F) {
+        let before = self.screen_to_world(point);
+        self.zoom *= factor;
+        let after = self.screen_to_world(point);
+
+        self.center += before - after;
+    }
+
+    /// Rotates the world counter-clockwise by [`angle`] radians, keeping the world location under
+    /// the given screen location in place
+    pub fn rotate_at(&mut self, point: CartesianPoint<F>, angle: F) {
+        let before = self.screen_to_world(point);
+        self.rotation += angle;
+        let after = self.screen_to_world(point);
+
+        self.center += before - after;
+    }
+
+    /// Returns the bottom left and top right corners of the smallest rectangle in world space
+    /// containing everything visible on the screen, for use with `Honeycomb::cells_in_rect`
+    pub fn visible_world_rect(&self) -> (CartesianPoint<F>, CartesianPoint<F>) {
+        let corners = [
+            (F::ZERO, F::ZERO),
+            (self.width, F::ZERO),
+            (F::ZERO, self.height),
+            (self.width, self.height),
+        ]
+        .map(|(x, y)| self.screen_to_world(CartesianPoint::new(x, y)));
+
+        let min_x = corners.iter().map(|p| p.x).fold(F::MAX, F::min);
+        let max_x = corners.iter().map(|p| p.x).fold(F::MIN, F::max);
+        let min_y = corners.iter().map(|p| p.y).fold(F::MAX, F::min);
+        let max_y = corners.iter().map(|p| p.y).fold(F::MIN, F::max);
+
+        (
+            CartesianPoint::new(min_x, min_y),
+            CartesianPoint::new(max_x, max_y),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartesian_point::{CartesianPoint, CartesianPoint64};
+    use crate::hex_cell::HexCell;
+    use crate::honeycomb::Honeycomb;
+    use crate::viewport::Viewport;
+
+    fn close(a: CartesianPoint, b: CartesianPoint) -> bool {
+        (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3
+    }
+
+    fn viewports() -> Vec<Viewport> {
+        let mut rotated = Viewport::new(800.0, 600.0, 25.0);
+        rotated.center = CartesianPoint::new(3.0, -2.0);
+        rotated.rotation = 0.7;
+
+        vec![Viewport::new(800.0, 600.0, 19.2), rotated]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for viewport in viewports() {
+            for (x, y) in [(0.0, 0.0), (400.0, 300.0), (123.0, 456.0), (799.0, 1.0)] {
+                let screen = CartesianPoint::new(x, y);
+                let world = viewport.screen_to_world(screen);
+
+                assert!(close(viewport.world_to_screen(world), screen));
+            }
+
+            let world = CartesianPoint::new(-7.5, 4.25);
+            assert!(close(
+                viewport.screen_to_world(viewport.world_to_screen(world)),
+                world
+            ));
+        }
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let honeycomb = Honeycomb::<()>::new(5);
+
+        for viewport in viewports() {
+            for h in honeycomb.grid.iter() {
+                let screen = viewport.hex_to_screen(h);
+
+                assert_eq!(viewport.screen_to_hex(screen), *h);
+                assert_eq!(
+                    honeycomb.world_to_hex(viewport.screen_to_world(screen)),
+                    Some(*h)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_orientation() {
+        let viewport = Viewport::new(800.0, 600.0, 10.0);
+
+        // World y points up, screen y points down
+        let up = viewport.world_to_screen(CartesianPoint::new(0.0, 1.0));
+        assert!(close(up, CartesianPoint::new(400.0, 290.0)));
+
+        let north_east = viewport.hex_to_screen(&HexCell::directions()[1]);
+        assert!(north_east.x > 400.0 && north_east.y < 300.0);
+    }
+
+    #[test]
+    fn test_pan_zoom_rotate() {
+        let mut viewport = Viewport::<f32>::new(800.0, 600.0, 10.0);
+        let cursor = CartesianPoint::new(650.0, 120.0);
+        let world = viewport.screen_to_world(cursor);
+
+        viewport.zoom_at(cursor, 2.5);
+        assert!((viewport.zoom - 25.0).abs() < 1e-4);
+        assert!(close(viewport.world_to_screen(world), cursor));
+
+        viewport.rotate_at(cursor, 1.2);
+        assert!(close(viewport.world_to_screen(world), cursor));
+
+        viewport.pan(30.0, -40.0);
+        assert!(close(
+            viewport.world_to_screen(world),
+            CartesianPoint::new(680.0, 80.0)
+        ));
+    }
+
+    #[test]
+    fn test_visible_world_rect() {
+        let honeycomb = Honeycomb::<()>::new(30);
+        let mut viewport = Viewport::new(800.0, 600.0, 40.0);
+        viewport.rotation = 0.3;
+
+        let (min, max) = viewport.visible_world_rect();
+        let visible = honeycomb.cells_in_rect(min, max);
+
+        for (x, y) in [
+            (0.0, 0.0),
+            (800.0, 0.0),
+            (0.0, 600.0),
+            (800.0, 600.0),
+            (400.0, 300.0),
+        ] {
+            let h = viewport.screen_to_hex(CartesianPoint::new(x, y));
+            assert!(visible.contains(&h));
+        }
+    }
+
+    #[test]
+    fn test_picking_far_from_origin() {
+        for center in [
+            HexCell::new(1_000_000, -500_000),
+            HexCell::new(-1_000_000, 1_000_000),
+        ] {
+            let mut viewport = Viewport::<f64>::new(800.0, 600.0, 40.0);
+            viewport.center = center.world_point();
+            viewport.rotation = 0.3;
+
+            for h in center.neighbors() {
+                let c = viewport.hex_to_screen(&h);
+                assert_eq!(viewport.screen_to_hex(c), h);
+
+                // Points close to the corners of the hexagon, less than a pixel from its border
+                for v in h.vertex_points::<f64>() {
+                    let p = viewport.world_to_screen(h.world_point::<f64>().lerp(&v, 0.97));
+                    assert_eq!(viewport.screen_to_hex(p), h);
+                }
+            }
+
+            let world = CartesianPoint64::new(1_234_567.25, -987_654.5);
+            let round_trip = viewport.screen_to_world(viewport.world_to_screen(world));
+            assert!(round_trip.approx_eq(&world, 1e-6));
+        }
+    }
+}